@@ -0,0 +1,62 @@
+//! Compares the dedup strategies in `problem1` against the original
+//! quadratic implementation. Run with `cargo bench --bench dedup`.
+
+extern crate hw01;
+
+mod common;
+
+use std::hint::black_box;
+
+use common::time;
+use hw01::problem1::{dedup, dedup_in_place, dedup_sorted};
+
+/// The original `dedup`, kept here as the baseline.
+fn dedup_quadratic(vs: &[i32]) -> Vec<i32> {
+    let mut result = Vec::new();
+
+    for e in vs {
+        if !result.contains(e) {
+            result.push(*e)
+        }
+    }
+    result
+}
+
+/// Pseudo-random ids with roughly `n / 4` distinct values.
+fn ids(n: usize) -> Vec<i32> {
+    let mut x = 0x2545_f491u32;
+    (0..n).map(|_| {
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        (x % (n as u32 / 4 + 1)) as i32
+    }).collect()
+}
+
+fn main() {
+    println!("{:>10} {:>14} {:>14} {:>14} {:>14}",
+             "n", "quadratic", "hashed", "in_place", "sorted");
+
+    for &n in &[1_000usize, 10_000, 100_000, 1_000_000] {
+        let vs = ids(n);
+        let mut sorted = vs.clone();
+        sorted.sort();
+
+        // The quadratic version takes minutes at a million elements.
+        let quadratic = if n <= 100_000 {
+            format!("{:?}", time(|| { black_box(dedup_quadratic(black_box(&vs))); }))
+        } else {
+            "-".to_string()
+        };
+        let hashed = time(|| { black_box(dedup(black_box(&vs))); });
+        let in_place = time(|| {
+            let mut copy = vs.clone();
+            dedup_in_place(&mut copy);
+            black_box(copy);
+        });
+        let sorted = time(|| { black_box(dedup_sorted(black_box(&sorted))); });
+
+        println!("{:>10} {:>14} {:>14?} {:>14?} {:>14?}",
+                 n, quadratic, hashed, in_place, sorted);
+    }
+}
@@ -0,0 +1,594 @@
+// hash.rs
+
+//! Non-cryptographic hash functions (plus SipHash), each available both as a
+//! streaming `Hasher` and as a plain `fn(&[u8]) -> u64`.
+//!
+//! Functions with a 32-bit output return it zero-extended to `u64`.
+
+use std::hash::Hasher;
+
+/// The common shape of the one-shot functions in this module, which is also
+/// what `problem4::bloom` takes.
+pub type HashFn = fn(&[u8]) -> u64;
+
+/// Every one-shot function in this module, with its name.
+pub const ALL: [(&str, HashFn); 9] = [
+    ("djb2", djb2),
+    ("fnv1_32", fnv1_32),
+    ("fnv1a_32", fnv1a_32),
+    ("fnv1_64", fnv1_64),
+    ("fnv1a_64", fnv1a_64),
+    ("one_at_a_time", one_at_a_time),
+    ("murmur3_32", murmur3_32),
+    ("xxh64", xxh64),
+    ("siphash24", siphash24),
+];
+
+fn hash_with<H: Hasher>(mut hasher: H, bytes: &[u8]) -> u64 {
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Bernstein's djb2, `hash * 33 + c`, over 64 bits.
+pub fn djb2(bytes: &[u8]) -> u64 { hash_with(Djb2Hasher::default(), bytes) }
+
+/// 32-bit FNV-1: multiply, then xor.
+pub fn fnv1_32(bytes: &[u8]) -> u64 { hash_with(Fnv1Hasher32::default(), bytes) }
+
+/// 32-bit FNV-1a: xor, then multiply.
+pub fn fnv1a_32(bytes: &[u8]) -> u64 { hash_with(Fnv1aHasher32::default(), bytes) }
+
+/// 64-bit FNV-1.
+pub fn fnv1_64(bytes: &[u8]) -> u64 { hash_with(Fnv1Hasher64::default(), bytes) }
+
+/// 64-bit FNV-1a.
+pub fn fnv1a_64(bytes: &[u8]) -> u64 { hash_with(Fnv1aHasher64::default(), bytes) }
+
+/// Bob Jenkins' 32-bit one-at-a-time hash.
+pub fn one_at_a_time(bytes: &[u8]) -> u64 { hash_with(OneAtATimeHasher::default(), bytes) }
+
+/// MurmurHash3 x86_32 with seed 0.
+pub fn murmur3_32(bytes: &[u8]) -> u64 { hash_with(Murmur3Hasher::default(), bytes) }
+
+/// xxHash64 with seed 0.
+pub fn xxh64(bytes: &[u8]) -> u64 { hash_with(XxHasher64::default(), bytes) }
+
+/// SipHash-2-4 with an all-zero key. Use `SipHasher24::new_with_keys` for a
+/// secret key.
+pub fn siphash24(bytes: &[u8]) -> u64 { hash_with(SipHasher24::default(), bytes) }
+
+//
+// djb2
+//
+
+/// Streaming `djb2`.
+#[derive(Clone, Copy, Debug)]
+pub struct Djb2Hasher(u64);
+
+impl Default for Djb2Hasher {
+    fn default() -> Self { Djb2Hasher(5381) }
+}
+
+impl Hasher for Djb2Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            // hash * 33 + c
+            self.0 = (self.0 << 5).wrapping_add(self.0).wrapping_add(b as u64);
+        }
+    }
+
+    fn finish(&self) -> u64 { self.0 }
+}
+
+//
+// FNV
+//
+
+const FNV32_OFFSET: u32 = 0x811c_9dc5;
+const FNV32_PRIME: u32 = 0x0100_0193;
+const FNV64_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV64_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Streaming `fnv1_32`.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv1Hasher32(u32);
+
+impl Default for Fnv1Hasher32 {
+    fn default() -> Self { Fnv1Hasher32(FNV32_OFFSET) }
+}
+
+impl Hasher for Fnv1Hasher32 {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = self.0.wrapping_mul(FNV32_PRIME) ^ b as u32;
+        }
+    }
+
+    fn finish(&self) -> u64 { self.0 as u64 }
+}
+
+/// Streaming `fnv1a_32`.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv1aHasher32(u32);
+
+impl Default for Fnv1aHasher32 {
+    fn default() -> Self { Fnv1aHasher32(FNV32_OFFSET) }
+}
+
+impl Hasher for Fnv1aHasher32 {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u32).wrapping_mul(FNV32_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 { self.0 as u64 }
+}
+
+/// Streaming `fnv1_64`.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv1Hasher64(u64);
+
+impl Default for Fnv1Hasher64 {
+    fn default() -> Self { Fnv1Hasher64(FNV64_OFFSET) }
+}
+
+impl Hasher for Fnv1Hasher64 {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = self.0.wrapping_mul(FNV64_PRIME) ^ b as u64;
+        }
+    }
+
+    fn finish(&self) -> u64 { self.0 }
+}
+
+/// Streaming `fnv1a_64`.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv1aHasher64(u64);
+
+impl Default for Fnv1aHasher64 {
+    fn default() -> Self { Fnv1aHasher64(FNV64_OFFSET) }
+}
+
+impl Hasher for Fnv1aHasher64 {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(FNV64_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 { self.0 }
+}
+
+//
+// Jenkins one-at-a-time
+//
+
+/// Streaming `one_at_a_time`. The final avalanche is applied in `finish`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OneAtATimeHasher(u32);
+
+impl Hasher for OneAtATimeHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = self.0.wrapping_add(b as u32);
+            self.0 = self.0.wrapping_add(self.0 << 10);
+            self.0 ^= self.0 >> 6;
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = self.0;
+        hash = hash.wrapping_add(hash << 3);
+        hash ^= hash >> 11;
+        hash = hash.wrapping_add(hash << 15);
+        hash as u64
+    }
+}
+
+//
+// MurmurHash3
+//
+
+/// Streaming `murmur3_32`, with a configurable seed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Murmur3Hasher {
+    hash: u32,
+    /// Bytes that don't yet fill a 4-byte block.
+    tail: [u8; 4],
+    tail_len: usize,
+    len: u64,
+}
+
+impl Murmur3Hasher {
+    pub fn with_seed(seed: u32) -> Self {
+        Murmur3Hasher { hash: seed, ..Default::default() }
+    }
+
+    fn mix_block(k: u32) -> u32 {
+        k.wrapping_mul(0xcc9e_2d51).rotate_left(15).wrapping_mul(0x1b87_3593)
+    }
+
+    fn block(&mut self, block: &[u8]) {
+        let k = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+        self.hash ^= Murmur3Hasher::mix_block(k);
+        self.hash = self.hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+}
+
+impl Hasher for Murmur3Hasher {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        if self.tail_len > 0 {
+            let take = (4 - self.tail_len).min(bytes.len());
+            self.tail[self.tail_len..self.tail_len + take].copy_from_slice(&bytes[..take]);
+            self.tail_len += take;
+            bytes = &bytes[take..];
+            if self.tail_len < 4 {
+                return;
+            }
+            let tail = self.tail;
+            self.block(&tail);
+            self.tail_len = 0;
+        }
+        let mut blocks = bytes.chunks_exact(4);
+        for block in &mut blocks {
+            self.block(block);
+        }
+        let rest = blocks.remainder();
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = self.hash;
+        if self.tail_len > 0 {
+            let k = self.tail[..self.tail_len].iter().rev()
+                .fold(0u32, |k, &b| (k << 8) | b as u32);
+            hash ^= Murmur3Hasher::mix_block(k);
+        }
+        // Only the low 32 bits of the length take part, as in the reference.
+        hash ^= self.len as u32;
+        hash ^= hash >> 16;
+        hash = hash.wrapping_mul(0x85eb_ca6b);
+        hash ^= hash >> 13;
+        hash = hash.wrapping_mul(0xc2b2_ae35);
+        hash ^= hash >> 16;
+        hash as u64
+    }
+}
+
+//
+// xxHash64
+//
+
+const XXH_PRIME_1: u64 = 0x9e37_79b1_85eb_ca87;
+const XXH_PRIME_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const XXH_PRIME_3: u64 = 0x1656_67b1_9e37_79f9;
+const XXH_PRIME_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const XXH_PRIME_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+/// Streaming `xxh64`, with a configurable seed.
+#[derive(Clone, Copy, Debug)]
+pub struct XxHasher64 {
+    seed: u64,
+    acc: [u64; 4],
+    /// Bytes that don't yet fill a 32-byte stripe.
+    buf: [u8; 32],
+    buf_len: usize,
+    len: u64,
+}
+
+impl Default for XxHasher64 {
+    fn default() -> Self { XxHasher64::with_seed(0) }
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(word)
+}
+
+fn xxh_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(XXH_PRIME_2)).rotate_left(31).wrapping_mul(XXH_PRIME_1)
+}
+
+impl XxHasher64 {
+    pub fn with_seed(seed: u64) -> Self {
+        XxHasher64 {
+            seed,
+            acc: [
+                seed.wrapping_add(XXH_PRIME_1).wrapping_add(XXH_PRIME_2),
+                seed.wrapping_add(XXH_PRIME_2),
+                seed,
+                seed.wrapping_sub(XXH_PRIME_1),
+            ],
+            buf: [0; 32],
+            buf_len: 0,
+            len: 0,
+        }
+    }
+
+    fn stripe(&mut self, stripe: &[u8]) {
+        for (i, acc) in self.acc.iter_mut().enumerate() {
+            *acc = xxh_round(*acc, read_u64(&stripe[8 * i..]));
+        }
+    }
+}
+
+impl Hasher for XxHasher64 {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        if self.buf_len > 0 {
+            let take = (32 - self.buf_len).min(bytes.len());
+            self.buf[self.buf_len..self.buf_len + take].copy_from_slice(&bytes[..take]);
+            self.buf_len += take;
+            bytes = &bytes[take..];
+            if self.buf_len < 32 {
+                return;
+            }
+            let buf = self.buf;
+            self.stripe(&buf);
+            self.buf_len = 0;
+        }
+        let mut stripes = bytes.chunks_exact(32);
+        for stripe in &mut stripes {
+            self.stripe(stripe);
+        }
+        let rest = stripes.remainder();
+        self.buf[..rest.len()].copy_from_slice(rest);
+        self.buf_len = rest.len();
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = if self.len >= 32 {
+            let [a, b, c, d] = self.acc;
+            let mut hash = a.rotate_left(1).wrapping_add(b.rotate_left(7))
+                .wrapping_add(c.rotate_left(12)).wrapping_add(d.rotate_left(18));
+            for &acc in &self.acc {
+                hash ^= xxh_round(0, acc);
+                hash = hash.wrapping_mul(XXH_PRIME_1).wrapping_add(XXH_PRIME_4);
+            }
+            hash
+        } else {
+            self.seed.wrapping_add(XXH_PRIME_5)
+        };
+        hash = hash.wrapping_add(self.len);
+
+        let mut rest = &self.buf[..self.buf_len];
+        while rest.len() >= 8 {
+            hash ^= xxh_round(0, read_u64(rest));
+            hash = hash.rotate_left(27).wrapping_mul(XXH_PRIME_1).wrapping_add(XXH_PRIME_4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            let word = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            hash ^= (word as u64).wrapping_mul(XXH_PRIME_1);
+            hash = hash.rotate_left(23).wrapping_mul(XXH_PRIME_2).wrapping_add(XXH_PRIME_3);
+            rest = &rest[4..];
+        }
+        for &b in rest {
+            hash ^= (b as u64).wrapping_mul(XXH_PRIME_5);
+            hash = hash.rotate_left(11).wrapping_mul(XXH_PRIME_1);
+        }
+
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(XXH_PRIME_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(XXH_PRIME_3);
+        hash ^= hash >> 32;
+        hash
+    }
+}
+
+//
+// SipHash
+//
+
+/// Streaming SipHash-2-4, keyed with two 64-bit halves of a 128-bit key.
+///
+/// This is the same function as the standard library's (deprecated)
+/// `SipHasher`, kept here so its output is pinned to the published
+/// reference rather than to whatever `std` does next.
+#[derive(Clone, Copy, Debug)]
+pub struct SipHasher24 {
+    v: [u64; 4],
+    /// Bytes that don't yet fill a 64-bit word, little-endian.
+    tail: u64,
+    tail_len: usize,
+    len: u64,
+}
+
+impl Default for SipHasher24 {
+    fn default() -> Self { SipHasher24::new_with_keys(0, 0) }
+}
+
+impl SipHasher24 {
+    pub fn new_with_keys(k0: u64, k1: u64) -> Self {
+        SipHasher24 {
+            v: [
+                k0 ^ 0x736f_6d65_7073_6575,
+                k1 ^ 0x646f_7261_6e64_6f6d,
+                k0 ^ 0x6c79_6765_6e65_7261,
+                k1 ^ 0x7465_6462_7974_6573,
+            ],
+            tail: 0,
+            tail_len: 0,
+            len: 0,
+        }
+    }
+
+    fn rounds(v: &mut [u64; 4], n: usize) {
+        for _ in 0..n {
+            v[0] = v[0].wrapping_add(v[1]);
+            v[1] = v[1].rotate_left(13) ^ v[0];
+            v[0] = v[0].rotate_left(32);
+            v[2] = v[2].wrapping_add(v[3]);
+            v[3] = v[3].rotate_left(16) ^ v[2];
+            v[0] = v[0].wrapping_add(v[3]);
+            v[3] = v[3].rotate_left(21) ^ v[0];
+            v[2] = v[2].wrapping_add(v[1]);
+            v[1] = v[1].rotate_left(17) ^ v[2];
+            v[2] = v[2].rotate_left(32);
+        }
+    }
+
+    fn word(&mut self, m: u64) {
+        self.v[3] ^= m;
+        SipHasher24::rounds(&mut self.v, 2);
+        self.v[0] ^= m;
+    }
+}
+
+impl Hasher for SipHasher24 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        for &b in bytes {
+            self.tail |= (b as u64) << (8 * self.tail_len);
+            self.tail_len += 1;
+            if self.tail_len == 8 {
+                let m = self.tail;
+                self.word(m);
+                self.tail = 0;
+                self.tail_len = 0;
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut v = self.v;
+        let b = (self.len << 56) | self.tail;
+        v[3] ^= b;
+        SipHasher24::rounds(&mut v, 2);
+        v[0] ^= b;
+        v[2] ^= 0xff;
+        SipHasher24::rounds(&mut v, 4);
+        v[0] ^ v[1] ^ v[2] ^ v[3]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_djb2() {
+        assert_eq!(djb2(b""), 5381);
+        assert_eq!(djb2(b"a"), 177_670);
+        assert_eq!(djb2(b"ab"), 5_863_208);
+    }
+
+    #[test]
+    fn test_fnv() {
+        // From the FNV reference test suite.
+        assert_eq!(fnv1_32(b""), 0x811c_9dc5);
+        assert_eq!(fnv1_32(b"a"), 0x050c_5d7e);
+        assert_eq!(fnv1_32(b"foobar"), 0x31f0_b262);
+        assert_eq!(fnv1a_32(b""), 0x811c_9dc5);
+        assert_eq!(fnv1a_32(b"a"), 0xe40c_292c);
+        assert_eq!(fnv1a_32(b"foobar"), 0xbf9c_f968);
+        assert_eq!(fnv1_64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1_64(b"a"), 0xaf63_bd4c_8601_b7be);
+        assert_eq!(fnv1_64(b"foobar"), 0x340d_8765_a4dd_a9c2);
+        assert_eq!(fnv1a_64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a_64(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn test_one_at_a_time() {
+        assert_eq!(one_at_a_time(b""), 0);
+        assert_eq!(one_at_a_time(b"a"), 0xca2e_9442);
+        assert_eq!(one_at_a_time(b"The quick brown fox jumps over the lazy dog"), 0x519e_91f5);
+    }
+
+    #[test]
+    fn test_murmur3() {
+        let seeded = |bytes: &[u8], seed| hash_with(Murmur3Hasher::with_seed(seed), bytes);
+        assert_eq!(murmur3_32(b""), 0);
+        assert_eq!(seeded(b"", 1), 0x514e_28b7);
+        assert_eq!(seeded(b"", 0xffff_ffff), 0x81f1_6f39);
+        assert_eq!(seeded(&[0xff, 0xff, 0xff, 0xff], 0), 0x7629_3b50);
+        assert_eq!(seeded(&[0x21, 0x43, 0x65, 0x87], 0x5082_edee), 0x2362_f9de);
+        assert_eq!(seeded(&[0x21, 0x43, 0x65], 0), 0x7e4a_8634);
+        assert_eq!(seeded(&[0x21, 0x43], 0), 0xa0f7_b07a);
+        assert_eq!(seeded(&[0x21], 0), 0x7266_1cf4);
+        assert_eq!(murmur3_32(b"hello"), 0x248b_fa47);
+        assert_eq!(murmur3_32(b"The quick brown fox jumps over the lazy dog"), 0x2e4f_f723);
+    }
+
+    #[test]
+    fn test_xxh64() {
+        assert_eq!(xxh64(b""), 0xef46_db37_51d8_e999);
+        assert_eq!(xxh64(b"a"), 0xd24e_c4f1_a98c_6e5b);
+        assert_eq!(xxh64(b"abc"), 0x44bc_2cf5_ad77_0999);
+        assert_eq!(xxh64(b"Nobody inspects the spammish repetition"), 0xfbce_a83c_8a37_8bf1);
+        assert_eq!(hash_with(XxHasher64::with_seed(1), b""), 0xd5af_ba13_36a3_be4b);
+    }
+
+    #[test]
+    fn test_siphash24() {
+        // The reference vectors use the key 00 01 02 ... 0f and the messages
+        // 00 01 02 ... (n - 1) for n = 0..64.
+        let key = |bytes: &[u8]| {
+            let mut hasher = SipHasher24::new_with_keys(0x0706_0504_0302_0100,
+                                                        0x0f0e_0d0c_0b0a_0908);
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        let message: Vec<u8> = (0..64).collect();
+        assert_eq!(key(&message[..0]), 0x726f_db47_dd0e_0e31);
+        assert_eq!(key(&message[..1]), 0x74f8_39c5_93dc_67fd);
+        assert_eq!(key(&message[..8]), 0x93f5_f579_9a93_2462);
+        assert_eq!(key(&message[..15]), 0xa129_ca61_49be_45e5);
+        assert_eq!(key(&message[..63]), 0x958a_324c_eb06_4572);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_siphash24_matches_std() {
+        use std::hash::SipHasher;
+        for bytes in &[&b""[..], b"a", b"a somewhat longer string of bytes"] {
+            let mut std_hasher = SipHasher::new_with_keys(7, 11);
+            std_hasher.write(bytes);
+            let ours = hash_with(SipHasher24::new_with_keys(7, 11), bytes);
+            assert_eq!(ours, std_hasher.finish());
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        // Split at every point, so partial blocks get carried between writes.
+        let bytes: Vec<u8> = (0..100u32).map(|i| (i * 37 % 251) as u8).collect();
+        fn boxed<H: Hasher + Default + 'static>() -> Box<dyn Hasher> {
+            Box::new(H::default())
+        }
+        type NewHasher = fn() -> Box<dyn Hasher>;
+        let hashers: [(&str, NewHasher); 9] = [
+            ("djb2", boxed::<Djb2Hasher>),
+            ("fnv1_32", boxed::<Fnv1Hasher32>),
+            ("fnv1a_32", boxed::<Fnv1aHasher32>),
+            ("fnv1_64", boxed::<Fnv1Hasher64>),
+            ("fnv1a_64", boxed::<Fnv1aHasher64>),
+            ("one_at_a_time", boxed::<OneAtATimeHasher>),
+            ("murmur3_32", boxed::<Murmur3Hasher>),
+            ("xxh64", boxed::<XxHasher64>),
+            ("siphash24", boxed::<SipHasher24>),
+        ];
+        for (&(name, f), &(hasher_name, new_hasher)) in ALL.iter().zip(&hashers) {
+            assert_eq!(name, hasher_name);
+            for len in 0..bytes.len() {
+                let bytes = &bytes[..len];
+                let expected = f(bytes);
+                for split in 0..len + 1 {
+                    let mut hasher = new_hasher();
+                    hasher.write(&bytes[..split]);
+                    hasher.write(&bytes[split..]);
+                    assert_eq!(hasher.finish(), expected, "{} of {} bytes split at {}",
+                               name, len, split);
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,10 @@
+pub mod hash;
 pub mod problem1;
+pub mod problem2;
+pub mod problem3;
+pub mod problem4;
+pub mod tests_property;
 pub mod tests_provided;
-// pub mod problem2;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,497 @@
+//! Command-line access to the hw01 algorithms.
+//!
+//! Run `hw01 --help` for usage. Input is read line by line from the named
+//! files, or from stdin if there are none (or one is `-`).
+
+extern crate hw01;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process;
+
+use hw01::problem1::dedup_iter;
+use hw01::problem2::primes_in_range;
+use hw01::problem3::{frame_stewart_count, hanoi_pegs, moves};
+use hw01::problem4::BloomFilter;
+
+const USAGE: &str = "\
+usage: hw01 [--json] <command> [args]
+
+commands:
+  primes <n>                         primes less than n
+  hanoi <discs> [--pegs k]           moves solving the puzzle on k pegs (default 3)
+  dedup [file...]                    input lines without repeats, in order
+  bloom build <filter> [--fp-rate p] [file...]
+                                     write a bloom filter holding the input lines
+  bloom query <filter> [file...]     check each input line against a filter
+
+options:
+  --json                             write JSON instead of plain text
+
+exit status: 0 on success, 2 on bad arguments or input, 1 on other errors";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    let result = run(&args, &mut stdin.lock(), &mut out)
+        .and_then(|()| out.flush().map_err(Error::from));
+    match result {
+        Ok(()) => {}
+        // The reader went away (`hw01 primes 1000000 | head`): not an error.
+        Err(Error::Internal(ref e)) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(err) => {
+            eprintln!("hw01: {}", err);
+            if let Error::BadInput(_) = err {
+                eprintln!("run `hw01 --help` for usage");
+            }
+            process::exit(err.exit_code());
+        }
+    }
+}
+
+/// A CLI error.
+#[derive(Debug)]
+enum Error {
+    /// Bad arguments or malformed input: the caller's fault.
+    BadInput(String),
+    /// Anything else, such as failing to write the output.
+    Internal(io::Error),
+}
+
+impl Error {
+    fn exit_code(&self) -> i32 {
+        match *self {
+            Error::BadInput(_) => 2,
+            Error::Internal(_) => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Error::BadInput(ref msg) => write!(f, "{}", msg),
+            Error::Internal(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Internal(e)
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn bad_input<T, S: Into<String>>(msg: S) -> Result<T> {
+    Err(Error::BadInput(msg.into()))
+}
+
+/// Parsed command line: positional arguments and `--name value` options.
+struct Args {
+    json: bool,
+    positional: Vec<String>,
+    pegs: Option<String>,
+    fp_rate: Option<String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Args> {
+        let mut parsed = Args { json: false, positional: Vec::new(), pegs: None, fp_rate: None };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--json" => parsed.json = true,
+                "--pegs" | "--fp-rate" => {
+                    let value = match iter.next() {
+                        Some(value) => value.clone(),
+                        None => return bad_input(format!("{} needs a value", arg)),
+                    };
+                    if arg == "--pegs" {
+                        parsed.pegs = Some(value);
+                    } else {
+                        parsed.fp_rate = Some(value);
+                    }
+                }
+                _ if arg.starts_with("--") => {
+                    return bad_input(format!("unknown option `{}`", arg));
+                }
+                _ => parsed.positional.push(arg.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Positional argument `i`, which must be present.
+    fn required(&self, i: usize, name: &str) -> Result<&str> {
+        match self.positional.get(i) {
+            Some(arg) => Ok(arg),
+            None => bad_input(format!("missing <{}>", name)),
+        }
+    }
+
+    /// Positional arguments from `i` on, which are input files.
+    fn inputs(&self, i: usize) -> &[String] {
+        if self.positional.len() > i { &self.positional[i..] } else { &[] }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(s: &str, name: &str) -> Result<T> {
+    match s.parse() {
+        Ok(n) => Ok(n),
+        Err(_) => bad_input(format!("invalid {} `{}`", name, s)),
+    }
+}
+
+fn run(args: &[String], stdin: &mut dyn BufRead, out: &mut dyn Write) -> Result<()> {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        writeln!(out, "{}", USAGE)?;
+        return Ok(());
+    }
+    let args = Args::parse(args)?;
+    let command = match args.positional.first() {
+        Some(command) => command.as_str(),
+        None => return bad_input("missing command"),
+    };
+
+    match command {
+        "primes" => primes(&args, out),
+        "hanoi" => hanoi(&args, out),
+        "dedup" => dedup(&args, stdin, out),
+        "bloom" => match args.required(1, "build|query")? {
+            "build" => bloom_build(&args, stdin, out),
+            "query" => bloom_query(&args, stdin, out),
+            other => bad_input(format!("unknown bloom command `{}`", other)),
+        },
+        other => bad_input(format!("unknown command `{}`", other)),
+    }
+}
+
+fn primes(args: &Args, out: &mut dyn Write) -> Result<()> {
+    let n: u64 = parse_number(args.required(1, "n")?, "n")?;
+    let mut list = List::new(out, args.json)?;
+    for p in primes_in_range(0, n) {
+        list.push(&p.to_string(), &p.to_string())?;
+    }
+    list.finish()
+}
+
+/// Most moves `hanoi` builds in memory for more than three pegs.
+const MAX_COLLECTED_MOVES: u128 = 1 << 20;
+
+fn hanoi(args: &Args, out: &mut dyn Write) -> Result<()> {
+    let discs: u32 = parse_number(args.required(1, "discs")?, "number of discs")?;
+    let pegs: u8 = match args.pegs {
+        Some(ref pegs) => parse_number(pegs, "number of pegs")?,
+        None => 3,
+    };
+    if pegs < 3 {
+        return bad_input("need at least 3 pegs");
+    }
+    if pegs == 3 && discs >= 64 {
+        return bad_input("too many discs for 3 pegs");
+    }
+    // Three pegs stream their moves; more pegs build them all in memory.
+    if pegs > 3 && frame_stewart_count(discs, pegs) > MAX_COLLECTED_MOVES {
+        return bad_input("too many discs: the solution has too many moves to build");
+    }
+
+    let mut list = List::new(out, args.json)?;
+    let mut write = |(from, to): (u8, u8)| {
+        list.push(&format!("{} {}", from, to), &format!("[{},{}]", from, to))
+    };
+    if pegs == 3 {
+        moves(discs).try_for_each(&mut write)?;
+    } else {
+        hanoi_pegs(discs, pegs).into_iter().try_for_each(&mut write)?;
+    }
+    list.finish()
+}
+
+fn dedup(args: &Args, stdin: &mut dyn BufRead, out: &mut dyn Write) -> Result<()> {
+    let mut list = List::new(out, args.json)?;
+    let mut lines = Vec::new();
+    for_each_line(args.inputs(1), stdin, |line| {
+        lines.push(line);
+        Ok(())
+    })?;
+    for line in dedup_iter(lines) {
+        list.push(&line, &json_string(&line))?;
+    }
+    list.finish()
+}
+
+fn bloom_build(args: &Args, stdin: &mut dyn BufRead, out: &mut dyn Write) -> Result<()> {
+    let path = args.required(2, "filter")?;
+    let fp_rate: f64 = match args.fp_rate {
+        Some(ref rate) => parse_number(rate, "false positive rate")?,
+        None => 0.01,
+    };
+    if !(fp_rate > 0.0 && fp_rate < 1.0) {
+        return bad_input("false positive rate must be between 0 and 1");
+    }
+
+    let mut lines = Vec::new();
+    for_each_line(args.inputs(3), stdin, |line| {
+        lines.push(line);
+        Ok(())
+    })?;
+    let items: Vec<String> = dedup_iter(lines).collect();
+
+    let mut filter: BloomFilter<str> = BloomFilter::with_rate(items.len() as u64, fp_rate);
+    for item in &items {
+        filter.insert(item);
+    }
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    filter.write_to(&mut writer)?;
+    writer.flush()?;
+
+    if args.json {
+        writeln!(out, "{{\"items\":{},\"bits\":{},\"hashes\":{}}}",
+                 items.len(), filter.num_bits(), filter.num_hashes())?;
+    } else {
+        writeln!(out, "{} items, {} bits, {} hashes", items.len(), filter.num_bits(),
+                 filter.num_hashes())?;
+    }
+    Ok(())
+}
+
+fn bloom_query(args: &Args, stdin: &mut dyn BufRead, out: &mut dyn Write) -> Result<()> {
+    let path = args.required(2, "filter")?;
+    let file = open(path)?;
+    let filter: BloomFilter<str> = match BloomFilter::read_from(BufReader::new(file)) {
+        Ok(filter) => filter,
+        Err(e) => return bad_input(format!("{}: {}", path, e)),
+    };
+
+    let mut list = List::new(out, args.json)?;
+    for_each_line(args.inputs(3), stdin, |line| {
+        let present = filter.contains(&line);
+        list.push(&format!("{}\t{}", present, line),
+                  &format!("{{\"item\":{},\"present\":{}}}", json_string(&line), present))
+    })?;
+    list.finish()
+}
+
+fn open(path: &str) -> Result<File> {
+    File::open(path).map_err(|e| Error::BadInput(format!("{}: {}", path, e)))
+}
+
+/// Calls `f` with every line of the input files, or of stdin if there are
+/// none. Unreadable files and invalid UTF-8 are bad input.
+fn for_each_line<F>(inputs: &[String], stdin: &mut dyn BufRead, mut f: F) -> Result<()>
+    where F: FnMut(String) -> Result<()>
+{
+    let mut read = |name: &str, reader: &mut dyn BufRead| -> Result<()> {
+        for line in reader.lines() {
+            match line {
+                Ok(line) => f(line)?,
+                Err(ref e) if e.kind() == io::ErrorKind::InvalidData => {
+                    return bad_input(format!("{}: input is not valid UTF-8", name));
+                }
+                Err(e) => return Err(Error::Internal(e)),
+            }
+        }
+        Ok(())
+    };
+
+    if inputs.is_empty() {
+        return read("stdin", stdin);
+    }
+    for path in inputs {
+        if path == "-" {
+            read("stdin", stdin)?;
+        } else {
+            read(path, &mut BufReader::new(open(path)?))?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a list of values either one per line or as a JSON array.
+struct List<'a> {
+    out: &'a mut dyn Write,
+    json: bool,
+    empty: bool,
+}
+
+impl<'a> List<'a> {
+    fn new(out: &'a mut dyn Write, json: bool) -> Result<Self> {
+        if json {
+            write!(out, "[")?;
+        }
+        Ok(List { out, json, empty: true })
+    }
+
+    /// Writes one value, given both as a line of text and as JSON.
+    fn push(&mut self, text: &str, json: &str) -> Result<()> {
+        if !self.json {
+            writeln!(self.out, "{}", text)?;
+        } else if self.empty {
+            write!(self.out, "{}", json)?;
+        } else {
+            write!(self.out, ",{}", json)?;
+        }
+        self.empty = false;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        if self.json {
+            writeln!(self.out, "]")?;
+        }
+        Ok(())
+    }
+}
+
+/// `s` as a quoted JSON string.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Runs the CLI on `args` with `input` as stdin, returning the exit code
+    /// and stdout.
+    fn cli(args: &[&str], input: &str) -> (i32, String) {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let mut out = Vec::new();
+        let code = match run(&args, &mut input.as_bytes(), &mut out) {
+            Ok(()) => 0,
+            Err(e) => e.exit_code(),
+        };
+        (code, String::from_utf8(out).unwrap())
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("hw01-cli-{}-{}", process::id(), name))
+    }
+
+    #[test]
+    fn test_primes() {
+        assert_eq!(cli(&["primes", "12"], ""), (0, "2\n3\n5\n7\n11\n".to_string()));
+        assert_eq!(cli(&["--json", "primes", "12"], ""), (0, "[2,3,5,7,11]\n".to_string()));
+        assert_eq!(cli(&["primes", "2", "--json"], ""), (0, "[]\n".to_string()));
+    }
+
+    #[test]
+    fn test_hanoi() {
+        assert_eq!(cli(&["hanoi", "2"], ""), (0, "1 2\n1 3\n2 3\n".to_string()));
+        assert_eq!(cli(&["hanoi", "1", "--json"], ""), (0, "[[1,3]]\n".to_string()));
+        let (code, out) = cli(&["hanoi", "5", "--pegs", "4"], "");
+        assert_eq!(code, 0);
+        assert_eq!(out.lines().count(), 13);
+
+        // Rejected up front by the move count, not by running out of memory.
+        for &(discs, pegs) in &[("140", "4"), ("10000", "4"), ("2000000", "255"), ("4294967295", "5")] {
+            assert_eq!(cli(&["hanoi", discs, "--pegs", pegs], "").0, 2, "{} discs", discs);
+        }
+        let (code, out) = cli(&["hanoi", "40", "--pegs", "4"], "");
+        assert_eq!(code, 0);
+        assert_eq!(out.lines().count(), 2_817);
+    }
+
+    #[test]
+    fn test_dedup() {
+        let input = "b\na\nb\n\nc\na\n";
+        assert_eq!(cli(&["dedup"], input), (0, "b\na\n\nc\n".to_string()));
+        assert_eq!(cli(&["dedup", "--json"], "x\n\"q\"\nx\n"),
+                   (0, "[\"x\",\"\\\"q\\\"\"]\n".to_string()));
+    }
+
+    #[test]
+    fn test_bloom_build_and_query() {
+        let filter = temp_path("foods.bloom");
+        let filter = filter.to_str().unwrap();
+        let foods = "apple\nblueberry\ncarrot\napple\n";
+        assert_eq!(cli(&["bloom", "build", filter, "--fp-rate", "0.001"], foods),
+                   (0, "3 items, 44 bits, 10 hashes\n".to_string()));
+
+        let (code, out) = cli(&["bloom", "query", filter], "carrot\nbread\n");
+        assert_eq!(code, 0);
+        assert_eq!(out, "true\tcarrot\nfalse\tbread\n");
+
+        let input = temp_path("query.txt");
+        fs::write(&input, "apple\n").unwrap();
+        let (code, out) = cli(&["--json", "bloom", "query", filter, input.to_str().unwrap()], "");
+        assert_eq!(code, 0);
+        assert_eq!(out, "[{\"item\":\"apple\",\"present\":true}]\n");
+
+        fs::remove_file(filter).unwrap();
+        fs::remove_file(input).unwrap();
+    }
+
+    #[test]
+    fn test_bad_input_exit_code() {
+        assert_eq!(cli(&[], "").0, 2);
+        assert_eq!(cli(&["frobnicate"], "").0, 2);
+        assert_eq!(cli(&["primes"], "").0, 2);
+        assert_eq!(cli(&["primes", "-5"], "").0, 2);
+        assert_eq!(cli(&["primes", "10", "--verbose"], "").0, 2);
+        assert_eq!(cli(&["hanoi", "3", "--pegs"], "").0, 2);
+        assert_eq!(cli(&["hanoi", "3", "--pegs", "2"], "").0, 2);
+        assert_eq!(cli(&["dedup", "/nonexistent/input"], "").0, 2);
+        assert_eq!(cli(&["bloom", "build", "x", "--fp-rate", "1.5"], "").0, 2);
+        assert_eq!(cli(&["bloom", "query", "/nonexistent/filter"], "").0, 2);
+
+        // A file that isn't a filter.
+        let not_filter = temp_path("not-a-filter");
+        fs::write(&not_filter, "hello").unwrap();
+        assert_eq!(cli(&["bloom", "query", not_filter.to_str().unwrap()], "x\n").0, 2);
+        fs::remove_file(not_filter).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_bad_input() {
+        let args = vec!["dedup".to_string()];
+        let mut out = Vec::new();
+        let result = run(&args, &mut &[0x66, 0xff, b'\n'][..], &mut out);
+        assert_eq!(result.unwrap_err().exit_code(), 2);
+    }
+
+    #[test]
+    fn test_internal_error_exit_code() {
+        struct Broken;
+        impl Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> io::Result<()> { Ok(()) }
+        }
+
+        let args = vec!["primes".to_string(), "10".to_string()];
+        let result = run(&args, &mut &b""[..], &mut Broken);
+        assert_eq!(result.unwrap_err().exit_code(), 1);
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b\\c\td\u{1}"), "\"a\\\"b\\\\c\\td\\u0001\"");
+        assert_eq!(json_string("héllo"), "\"héllo\"");
+    }
+}
@@ -1,48 +1,754 @@
 // problem1.rs
 
-/// Functions are private (only available to this module) by default.
-/// Use the `pub` keyword to mark this function as public.
+use std::collections::{BTreeSet, HashSet};
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+use std::thread;
+
+// Functions are private (only available to this module) by default.
+// Use the `pub` keyword to mark this function as public.
 
 /// Computes the sum of all elements in the input i32 slice named `slice`
+///
+/// Overflow panics in debug builds and wraps in release builds. Use one of
+/// `checked_sum`, `saturating_sum`, `wrapping_sum` or `widening_sum` to
+/// pick a behavior explicitly.
 pub fn sum(slice: &[i32]) -> i32 {
-    if slice.len() == 0 { 0 }
-    else { slice[0] + sum(&slice[1..]) }
+    sum_iter(slice.iter().cloned())
 }
 
-// Imperative version
-// pub fn sum(slice: &[i32]) -> i32 {
-//     let mut sum = 0;
-    
-//     for e in slice {
-//         sum += *e
-//     }
-//     sum
-// }
-
 /// Deduplicates items in the input vector `vs`. Produces a vector containing
 /// the first instance of each distinc element of `vs`, preserving the
 /// original order
+///
+/// Runs in expected O(n) time (see `dedup_hashed`).
+#[allow(clippy::ptr_arg)]
 pub fn dedup(vs: &Vec<i32>) -> Vec<i32> {
+    dedup_hashed(vs)
+}
+
+/// Filters a vector `vs` using a predicate `pred` (a function from `i32` to
+/// `bool`). Returns a new vector containing only elements that satisfy `pred`.
+#[allow(clippy::ptr_arg)]
+pub fn filter(vs: &Vec<i32>, pred: &dyn Fn(i32) -> bool) -> Vec<i32> {
+    filter_by(vs, |e| pred(*e))
+}
+
+//
+// Overflow-aware sums
+//
+
+/// Sums `slice`, returning `None` if any partial sum (taken left to right)
+/// leaves the `i32` range.
+///
+/// `checked_sum(&[i32::MAX, 1, -1])` is `None` even though the total fits,
+/// because `i32::MAX + 1` overflows on the way.
+pub fn checked_sum(slice: &[i32]) -> Option<i32> {
+    checked_sum_iter(slice.iter().cloned())
+}
+
+/// Sums `slice` exactly and clamps the result to `i32::MIN..=i32::MAX`.
+///
+/// Unlike `saturating_sum_iter`, which saturates after every addition, the
+/// result doesn't depend on the order of the elements:
+/// `saturating_sum(&[i32::MAX, 1, -1])` is `i32::MAX`, and
+/// `saturating_sum(&[i32::MAX, 1, -2])` is `i32::MAX - 1`.
+pub fn saturating_sum(slice: &[i32]) -> i32 {
+    let total = widening_sum(slice);
+    if total > i32::MAX as i64 {
+        i32::MAX
+    } else if total < i32::MIN as i64 {
+        i32::MIN
+    } else {
+        total as i32
+    }
+}
+
+/// Sums `slice` modulo 2^32, in every build profile. Since wrapping addition
+/// is associative, this is the true sum wrapped once at the end:
+/// `wrapping_sum(&[i32::MAX, 1])` is `i32::MIN`.
+pub fn wrapping_sum(slice: &[i32]) -> i32 {
+    slice.iter().fold(0i32, |total, &e| total.wrapping_add(e))
+}
+
+/// Integer types with a wider type that their sums can be accumulated in.
+pub trait Widen: Copy {
+    type Wide: Zero + Add<Output = Self::Wide> + From<Self>;
+}
+
+macro_rules! impl_widen {
+    ($($t:ty => $wide:ty),*) => ($(
+        impl Widen for $t {
+            type Wide = $wide;
+        }
+    )*)
+}
+
+impl_widen! { i8 => i64, i16 => i64, i32 => i64, i64 => i128,
+              u8 => u64, u16 => u64, u32 => u64, u64 => u128 }
+
+/// Sums `slice` in the next wider type (`i64` for `i32`, `i128` for `i64`,
+/// and so on): `widening_sum(&[i32::MAX, i32::MAX])` is `4294967294i64`.
+/// Overflowing the wide type would take more than 2^32 elements.
+pub fn widening_sum<T: Widen>(slice: &[T]) -> T::Wide {
+    sum_iter(slice.iter().map(|&e| T::Wide::from(e)))
+}
+
+/// Floating-point types, for the compensated sums below.
+pub trait Float: Zero + Copy + PartialOrd + Add<Output = Self> + Sub<Output = Self> {
+    fn abs(self) -> Self;
+}
+
+impl Float for f32 {
+    fn abs(self) -> Self { f32::abs(self) }
+}
+
+impl Float for f64 {
+    fn abs(self) -> Self { f64::abs(self) }
+}
+
+/// Kahan summation: carries the low-order bits lost by each addition into
+/// the next one, so the error doesn't grow with the length of `slice`.
+///
+/// Kahan's method assumes the running total is larger than each term; see
+/// `neumaier_sum` for inputs where that isn't true.
+pub fn kahan_sum<T: Float>(slice: &[T]) -> T {
+    let mut total = T::zero();
+    let mut compensation = T::zero();
+    for &e in slice {
+        let y = e - compensation;
+        let t = total + y;
+        compensation = (t - total) - y;
+        total = t;
+    }
+    total
+}
+
+/// Neumaier's improvement of `kahan_sum`, which also compensates when a term
+/// is larger than the running total:
+/// `neumaier_sum(&[1.0, 1e100, 1.0, -1e100])` is `2.0`, where both plain and
+/// Kahan summation give `0.0`.
+pub fn neumaier_sum<T: Float>(slice: &[T]) -> T {
+    let mut total = T::zero();
+    let mut compensation = T::zero();
+    for &e in slice {
+        let t = total + e;
+        if total.abs() >= e.abs() {
+            compensation = compensation + ((total - t) + e);
+        } else {
+            compensation = compensation + ((e - t) + total);
+        }
+        total = t;
+    }
+    total + compensation
+}
+
+//
+// Generic versions
+//
+
+/// Types with an additive identity.
+pub trait Zero {
+    fn zero() -> Self;
+}
+
+/// Addition that reports overflow instead of panicking or wrapping.
+pub trait CheckedAdd: Sized {
+    fn checked_add(self, other: Self) -> Option<Self>;
+}
+
+/// Addition that clamps at the numeric bounds instead of overflowing.
+pub trait SaturatingAdd {
+    fn saturating_add(self, other: Self) -> Self;
+}
+
+macro_rules! impl_int_traits {
+    ($($t:ty)*) => ($(
+        impl Zero for $t {
+            fn zero() -> Self { 0 }
+        }
+
+        impl CheckedAdd for $t {
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+        }
+
+        impl SaturatingAdd for $t {
+            fn saturating_add(self, other: Self) -> Self {
+                <$t>::saturating_add(self, other)
+            }
+        }
+    )*)
+}
+
+impl_int_traits! { i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize }
+
+impl Zero for f32 {
+    fn zero() -> Self { 0.0 }
+}
+
+impl Zero for f64 {
+    fn zero() -> Self { 0.0 }
+}
+
+/// Sums every item produced by `iter`, starting from `T::zero()`.
+///
+/// Overflow behaves like the underlying `+`: it panics in debug builds and
+/// wraps in release builds. Use `checked_sum_iter` or `saturating_sum_iter`
+/// when that matters.
+pub fn sum_iter<I>(iter: I) -> I::Item
+    where I: IntoIterator,
+          I::Item: Zero + Add<Output = I::Item>
+{
+    let mut total = I::Item::zero();
+    for e in iter {
+        total = total + e;
+    }
+    total
+}
+
+/// Sums every item produced by `iter`, returning `None` as soon as an
+/// addition overflows.
+pub fn checked_sum_iter<I>(iter: I) -> Option<I::Item>
+    where I: IntoIterator,
+          I::Item: Zero + CheckedAdd
+{
+    let mut total = I::Item::zero();
+    for e in iter {
+        total = total.checked_add(e)?;
+    }
+    Some(total)
+}
+
+/// Sums every item produced by `iter`, clamping the running total at the
+/// numeric bounds of the item type.
+///
+/// Note that saturation is applied at every step, so the result depends on
+/// the order of the items when the total crosses a bound.
+pub fn saturating_sum_iter<I>(iter: I) -> I::Item
+    where I: IntoIterator,
+          I::Item: Zero + SaturatingAdd
+{
+    let mut total = I::Item::zero();
+    for e in iter {
+        total = total.saturating_add(e);
+    }
+    total
+}
+
+/// Deduplicates `vs` using a hash set, keeping the first instance of each
+/// distinct element in its original position.
+///
+/// Runs in expected O(n) time. The output order is stable: element `a`
+/// comes before element `b` in the result exactly when the first `a` comes
+/// before the first `b` in `vs`.
+pub fn dedup_hashed<T>(vs: &[T]) -> Vec<T>
+    where T: Eq + Hash + Clone
+{
+    let mut seen = HashSet::with_capacity(vs.len());
     let mut result = Vec::new();
-    
+
     for e in vs {
-        if !result.contains(e) {
-            result.push(*e)
+        if seen.insert(e) {
+            result.push(e.clone())
         }
     }
-    result   
+    result
 }
 
+/// Deduplicates a vector `vs` that is already sorted (or at least has all
+/// equal elements next to each other) in O(n) time, without hashing.
+pub fn dedup_sorted<T>(vs: &[T]) -> Vec<T>
+    where T: PartialEq + Clone
+{
+    let mut result: Vec<T> = Vec::new();
 
-/// Filters a vector `vs` using a predicate `pred` (a function from `i32` to
-/// `bool`). Returns a new vector containing only elements that satisfy `pred`.
-pub fn filter(vs: &Vec<i32>, pred: &Fn(i32) -> bool) -> Vec<i32> {
+    for e in vs {
+        if result.last() != Some(e) {
+            result.push(e.clone())
+        }
+    }
+    result
+}
+
+/// Keeps the first element of `vs` for each distinct value of `key`,
+/// preserving the original order. Runs in expected O(n) time.
+pub fn dedup_by_key<T, K, F>(vs: &[T], mut key: F) -> Vec<T>
+    where T: Clone,
+          K: Eq + Hash,
+          F: FnMut(&T) -> K
+{
+    let mut seen = HashSet::with_capacity(vs.len());
     let mut result = Vec::new();
 
     for e in vs {
-        if pred(*e) {
-            result.push(*e)
+        if seen.insert(key(e)) {
+            result.push(e.clone())
         }
     }
     result
 }
+
+/// Removes every element of `vs` that is equal to an earlier one, keeping
+/// the survivors in their original order. The vector is compacted in place
+/// and no element is cloned: a set of references into `vs` marks the first
+/// occurrences, and only those flags outlive it.
+pub fn dedup_in_place<T>(vs: &mut Vec<T>)
+    where T: Eq + Hash
+{
+    let keep: Vec<bool> = {
+        let mut seen = HashSet::with_capacity(vs.len());
+        vs.iter().map(|e| seen.insert(e)).collect()
+    };
+    // `retain` visits the elements exactly once, in order.
+    let mut keep = keep.into_iter();
+    vs.retain(|_| keep.next().unwrap());
+}
+
+/// Deduplicates `vs` using an ordered set, for element types that are `Ord`
+/// but not `Hash`. The first instance of each element keeps its position.
+pub fn dedup_ordered<T>(vs: &[T]) -> Vec<T>
+    where T: Ord + Clone
+{
+    dedup_ord_iter(vs.iter().cloned()).collect()
+}
+
+/// Returns a new vector containing the elements of `vs` that satisfy `pred`.
+pub fn filter_by<T, P>(vs: &[T], pred: P) -> Vec<T>
+    where T: Clone,
+          P: Fn(&T) -> bool
+{
+    filter_iter(vs.iter().cloned(), pred).collect()
+}
+
+//
+// Iterator adapters
+//
+
+/// Lazily yields the first occurrence of each distinct item of `iter`.
+/// Only the distinct items seen so far are kept in memory.
+pub fn dedup_iter<I>(iter: I) -> DedupIter<I::IntoIter>
+    where I: IntoIterator,
+          I::Item: Eq + Hash + Clone
+{
+    DedupIter { iter: iter.into_iter(), seen: HashSet::new() }
+}
+
+/// Like `dedup_iter`, but for `Ord` items.
+pub fn dedup_ord_iter<I>(iter: I) -> DedupOrdIter<I::IntoIter>
+    where I: IntoIterator,
+          I::Item: Ord + Clone
+{
+    DedupOrdIter { iter: iter.into_iter(), seen: BTreeSet::new() }
+}
+
+/// Lazily yields the items of `iter` that satisfy `pred`.
+pub fn filter_iter<I, P>(iter: I, pred: P) -> FilterIter<I::IntoIter, P>
+    where I: IntoIterator,
+          P: Fn(&I::Item) -> bool
+{
+    FilterIter { iter: iter.into_iter(), pred }
+}
+
+/// Iterator returned by `dedup_iter`.
+pub struct DedupIter<I: Iterator> {
+    iter: I,
+    seen: HashSet<I::Item>,
+}
+
+impl<I> Iterator for DedupIter<I>
+    where I: Iterator,
+          I::Item: Eq + Hash + Clone
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let seen = &mut self.seen;
+        self.iter.by_ref().find(|e| seen.insert(e.clone()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.iter.size_hint();
+        (if self.seen.is_empty() { lo.min(1) } else { 0 }, hi)
+    }
+}
+
+/// Iterator returned by `dedup_ord_iter`.
+pub struct DedupOrdIter<I: Iterator> {
+    iter: I,
+    seen: BTreeSet<I::Item>,
+}
+
+impl<I> Iterator for DedupOrdIter<I>
+    where I: Iterator,
+          I::Item: Ord + Clone
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let seen = &mut self.seen;
+        self.iter.by_ref().find(|e| seen.insert(e.clone()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.iter.size_hint();
+        (if self.seen.is_empty() { lo.min(1) } else { 0 }, hi)
+    }
+}
+
+/// Iterator returned by `filter_iter`.
+pub struct FilterIter<I, P> {
+    iter: I,
+    pred: P,
+}
+
+impl<I, P> Iterator for FilterIter<I, P>
+    where I: Iterator,
+          P: Fn(&I::Item) -> bool
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pred = &self.pred;
+        self.iter.by_ref().find(|e| pred(e))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+//
+// Parallel versions
+//
+
+/// How the `par_*` functions split their work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parallelism {
+    /// Number of threads to split the input across.
+    pub threads: usize,
+    /// Inputs shorter than this are processed sequentially on the calling
+    /// thread, where spawning threads would cost more than it saves.
+    pub threshold: usize,
+}
+
+impl Parallelism {
+    /// Splits work across `threads` threads, with the default threshold.
+    pub fn new(threads: usize) -> Self {
+        Parallelism { threads: threads.max(1), threshold: 10_000 }
+    }
+
+    /// Returns a copy that falls back to sequential below `threshold`
+    /// elements.
+    pub fn with_threshold(self, threshold: usize) -> Self {
+        Parallelism { threshold, ..self }
+    }
+
+    /// Splits `vs` into at most `threads` contiguous chunks, or returns
+    /// `None` if it should be processed sequentially.
+    fn chunks<'a, T>(&self, vs: &'a [T]) -> Option<::std::slice::Chunks<'a, T>> {
+        if self.threads <= 1 || vs.len() < self.threshold.max(2) {
+            None
+        } else {
+            Some(vs.chunks(vs.len().div_ceil(self.threads)))
+        }
+    }
+}
+
+impl Default for Parallelism {
+    /// One thread per available CPU.
+    fn default() -> Self {
+        Parallelism::new(thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+    }
+}
+
+/// Runs `f` on each chunk of `vs` on its own scoped thread and returns the
+/// results in chunk order.
+fn map_chunks<T, R, F>(chunks: ::std::slice::Chunks<T>, f: F) -> Vec<R>
+    where T: Sync,
+          R: Send,
+          F: Fn(&[T]) -> R + Sync
+{
+    let f = &f;
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks.map(|chunk| scope.spawn(move || f(chunk))).collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    })
+}
+
+/// Sums `slice` by summing contiguous chunks on separate threads and then
+/// adding up the partial sums.
+///
+/// For integers, addition is associative, so the result is the same as
+/// `sum_iter`, except that in debug builds the partial sums may overflow
+/// (and panic) where the sequential running total would not, or vice versa.
+/// Float addition is not associative: `f32` and `f64` results depend on how
+/// the slice is split and may differ from `sum_iter` in the last bits.
+pub fn par_sum<T>(slice: &[T], par: Parallelism) -> T
+    where T: Zero + Add<Output = T> + Copy + Send + Sync
+{
+    match par.chunks(slice) {
+        None => sum_iter(slice.iter().cloned()),
+        Some(chunks) => sum_iter(map_chunks(chunks, |c| sum_iter(c.iter().cloned()))),
+    }
+}
+
+/// Parallel `filter_by`: filters contiguous chunks on separate threads and
+/// concatenates the results, so the output order is the same.
+pub fn par_filter<T, P>(vs: &[T], pred: P, par: Parallelism) -> Vec<T>
+    where T: Clone + Send + Sync,
+          P: Fn(&T) -> bool + Sync
+{
+    match par.chunks(vs) {
+        None => filter_by(vs, pred),
+        Some(chunks) => map_chunks(chunks, |c| filter_by(c, &pred)).concat(),
+    }
+}
+
+/// Parallel `dedup_hashed`, with the same stable output order.
+///
+/// Each chunk is deduplicated on its own thread; the chunk results are then
+/// merged in order, keeping only values not already seen in an earlier
+/// chunk. The first occurrence of a value overall is its first occurrence
+/// in the earliest chunk that contains it, so nothing moves.
+pub fn par_dedup<T>(vs: &[T], par: Parallelism) -> Vec<T>
+    where T: Eq + Hash + Clone + Send + Sync
+{
+    let chunks = match par.chunks(vs) {
+        None => return dedup_hashed(vs),
+        Some(chunks) => chunks,
+    };
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    for chunk in map_chunks(chunks, dedup_hashed) {
+        for e in chunk {
+            if !seen.contains(&e) {
+                seen.insert(e.clone());
+                result.push(e);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sum_generic() {
+        assert_eq!(sum_iter(vec![1i64 << 40, 1i64 << 40]), 1i64 << 41);
+        assert_eq!(sum_iter(vec![0.5f64, 0.25, 0.25]), 1.0);
+        assert_eq!(sum_iter(Vec::<u8>::new()), 0);
+        assert_eq!(sum_iter(1..101u64), 5050);
+    }
+
+    #[test]
+    fn test_sum_overflow_variants() {
+        assert_eq!(checked_sum_iter(vec![i32::MAX, 1]), None);
+        assert_eq!(checked_sum_iter(vec![i32::MAX, -1, 1]), Some(i32::MAX));
+        assert_eq!(saturating_sum_iter(vec![250u8, 10]), 255);
+        assert_eq!(saturating_sum_iter(vec![i32::MIN, -1, 5]), i32::MIN + 5);
+    }
+
+    #[test]
+    fn test_checked_sum_boundaries() {
+        assert_eq!(checked_sum(&[]), Some(0));
+        assert_eq!(checked_sum(&[i32::MAX]), Some(i32::MAX));
+        assert_eq!(checked_sum(&[i32::MAX - 1, 1]), Some(i32::MAX));
+        assert_eq!(checked_sum(&[i32::MAX, 1]), None);
+        assert_eq!(checked_sum(&[i32::MIN, -1]), None);
+        assert_eq!(checked_sum(&[i32::MAX, i32::MIN]), Some(-1));
+        // Overflow on the way counts, even if the total would fit.
+        assert_eq!(checked_sum(&[i32::MAX, 1, -1]), None);
+        assert_eq!(checked_sum(&[i32::MAX, -1, 1]), Some(i32::MAX));
+    }
+
+    #[test]
+    fn test_saturating_sum_boundaries() {
+        assert_eq!(saturating_sum(&[i32::MAX, 1]), i32::MAX);
+        assert_eq!(saturating_sum(&[i32::MAX, i32::MAX, i32::MAX]), i32::MAX);
+        assert_eq!(saturating_sum(&[i32::MIN, -1]), i32::MIN);
+        assert_eq!(saturating_sum(&[i32::MAX, 1, -2]), i32::MAX - 1);
+        assert_eq!(saturating_sum(&[1, -2, i32::MAX]), i32::MAX - 1);
+        // Saturating at every step instead depends on the order.
+        assert_eq!(saturating_sum_iter(vec![i32::MAX, 1, -2]), i32::MAX - 2);
+    }
+
+    #[test]
+    fn test_wrapping_sum_boundaries() {
+        assert_eq!(wrapping_sum(&[i32::MAX, 1]), i32::MIN);
+        assert_eq!(wrapping_sum(&[i32::MIN, -1]), i32::MAX);
+        assert_eq!(wrapping_sum(&[i32::MAX, 1, -1]), i32::MAX);
+        assert_eq!(wrapping_sum(&[i32::MAX; 4]), -4);
+    }
+
+    #[test]
+    fn test_widening_sum_boundaries() {
+        assert_eq!(widening_sum(&[i32::MAX, 1]), i32::MAX as i64 + 1);
+        assert_eq!(widening_sum(&[i32::MAX, i32::MAX]), 4_294_967_294i64);
+        assert_eq!(widening_sum(&[i32::MIN; 3]), 3 * i32::MIN as i64);
+        assert_eq!(widening_sum(&[i64::MAX, i64::MAX]), 2 * i64::MAX as i128);
+        assert_eq!(widening_sum(&[u32::MAX, 1]), 1u64 << 32);
+        assert_eq!(widening_sum::<u8>(&[]), 0u64);
+    }
+
+    #[test]
+    fn test_compensated_sums() {
+        // 0.1 is not representable, so naive summation drifts.
+        let tenths = vec![0.1f64; 10_000];
+        let naive: f64 = sum_iter(tenths.iter().cloned());
+        assert!((naive - 1000.0).abs() > 1e-10);
+        assert_eq!(kahan_sum(&tenths), 1000.0);
+        assert_eq!(neumaier_sum(&tenths), 1000.0);
+
+        let cancelling = [1.0f64, 1e100, 1.0, -1e100];
+        assert_eq!(kahan_sum(&cancelling), 0.0);
+        assert_eq!(neumaier_sum(&cancelling), 2.0);
+
+        let small = vec![0.1f32; 10_000];
+        assert_eq!(neumaier_sum(&small), 1000.0);
+    }
+
+    #[test]
+    fn test_sum_large_slice() {
+        // The old recursive `sum` overflowed the stack well before this.
+        let vs = vec![1; 1_000_000];
+        assert_eq!(sum(&vs), 1_000_000);
+    }
+
+    #[test]
+    fn test_dedup_generic() {
+        let words = vec!["b".to_string(), "a".to_string(), "b".to_string()];
+        assert_eq!(dedup_hashed(&words), vec!["b".to_string(), "a".to_string()]);
+        assert_eq!(dedup_ordered(&words), vec!["b".to_string(), "a".to_string()]);
+        assert_eq!(dedup_ordered(&[3i64, 1, 3, 2, 1]), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn test_dedup_iter_is_lazy() {
+        // An unbounded stream: only as much as we take is ever consumed.
+        let cycled = (0..5u32).cycle();
+        let firsts: Vec<u32> = dedup_iter(cycled).take(5).collect();
+        assert_eq!(firsts, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_dedup_keeps_first_occurrence_order() {
+        // Many repeats of a reversed range: the first occurrences are the
+        // reversed range itself.
+        let vs: Vec<i32> = (0..200_000).map(|i| 999 - i % 1000).collect();
+        let expected: Vec<i32> = (0..1000).rev().collect();
+        assert_eq!(dedup(&vs), expected);
+
+        let mut in_place = vs.clone();
+        dedup_in_place(&mut in_place);
+        assert_eq!(in_place, expected);
+    }
+
+    #[test]
+    fn test_dedup_sorted() {
+        assert_eq!(dedup_sorted(&[1, 1, 2, 3, 3, 3, 7]), vec![1, 2, 3, 7]);
+        assert_eq!(dedup_sorted::<i32>(&[]), vec![]);
+        // Only adjacent duplicates are removed.
+        assert_eq!(dedup_sorted(&[1, 2, 1]), vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn test_dedup_by_key() {
+        let people = vec![("ann", 31), ("bob", 25), ("alice", 31), ("carl", 40)];
+        assert_eq!(dedup_by_key(&people, |p| p.1),
+                   vec![("ann", 31), ("bob", 25), ("carl", 40)]);
+        assert_eq!(dedup_by_key(&people, |p| p.0.len()),
+                   vec![("ann", 31), ("alice", 31), ("carl", 40)]);
+    }
+
+    #[test]
+    fn test_dedup_in_place() {
+        let mut vs = vec![3, 1, 3, 3, 2, 1];
+        let cap = vs.capacity();
+        dedup_in_place(&mut vs);
+        assert_eq!(vs, vec![3, 1, 2]);
+        assert_eq!(vs.capacity(), cap);
+
+        // No `Clone` needed.
+        #[derive(Debug, PartialEq, Eq, Hash)]
+        struct Token(String);
+        let tokens = |words: &[&str]| -> Vec<Token> {
+            words.iter().map(|w| Token(w.to_string())).collect()
+        };
+        let mut vs = tokens(&["a", "b", "a", "c", "b"]);
+        dedup_in_place(&mut vs);
+        assert_eq!(vs, tokens(&["a", "b", "c"]));
+    }
+
+    /// xorshift64*, so the randomized tests below need no dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545f4914f6cdd1d)
+        }
+
+        fn vec(&mut self, len: usize, range: i32) -> Vec<i32> {
+            (0..len).map(|_| (self.next() % range as u64) as i32 - range / 2).collect()
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        for round in 0..200 {
+            let len = (rng.next() % 2000) as usize;
+            let range = 1 + (rng.next() % 500) as i32;
+            let vs = rng.vec(len, range);
+            let par = Parallelism::new(1 + round % 9).with_threshold(round % 50);
+
+            assert_eq!(par_sum(&vs, par), sum(&vs));
+            let modulus = 1 + range % 7;
+            assert_eq!(par_filter(&vs, |x| x % modulus == 0, par),
+                       filter_by(&vs, |x| x % modulus == 0));
+            assert_eq!(par_dedup(&vs, par), dedup(&vs));
+        }
+    }
+
+    #[test]
+    fn test_parallel_large_input() {
+        let mut rng = Rng(42);
+        let vs = rng.vec(1_000_000, 100_000);
+        let par = Parallelism::default();
+        assert_eq!(par_sum(&vs, par), sum(&vs));
+        assert_eq!(par_filter(&vs, |x| x % 3 == 0, par), filter_by(&vs, |x| x % 3 == 0));
+        assert_eq!(par_dedup(&vs, par), dedup(&vs));
+
+        let floats: Vec<f64> = (0..100_000).map(|i| i as f64).collect();
+        assert_eq!(par_sum(&floats, Parallelism::new(4)), 4_999_950_000.0);
+    }
+
+    #[test]
+    fn test_parallel_edge_cases() {
+        let par = Parallelism::new(8).with_threshold(0);
+        assert_eq!(par_sum::<i32>(&[], par), 0);
+        assert_eq!(par_dedup::<i32>(&[], par), vec![]);
+        assert_eq!(par_filter(&[5], |_| true, par), vec![5]);
+        // More threads than elements.
+        assert_eq!(par_dedup(&[2, 1, 2], par), vec![2, 1]);
+        assert_eq!(Parallelism::new(0).threads, 1);
+    }
+
+    #[test]
+    fn test_filter_generic() {
+        let names = vec!["ann", "bob", "alice"];
+        assert_eq!(filter_by(&names, |s| s.starts_with('a')), vec!["ann", "alice"]);
+
+        let floats = [1.5f64, -2.0, 3.25];
+        assert_eq!(filter_by(&floats, |x| *x > 0.0), vec![1.5, 3.25]);
+
+        let evens: Vec<u64> = filter_iter(0.., |x| x % 2 == 0).take(3).collect();
+        assert_eq!(evens, vec![0, 2, 4]);
+    }
+}
@@ -1,18 +1,308 @@
+// problem2.rs
+
+use std::cmp;
+
+/// Number of odd numbers covered by one segment of the segmented sieve.
+/// One bit per odd number, so a segment is 32 KiB: small enough to stay in
+/// L1/L2 cache while it is being crossed out.
+const SEGMENT_ODDS: u64 = 1 << 18;
+
 /// Find all prime numbers less than `n`.
 /// For example, `sieve(7)` should return `[2, 3, 5]`
 pub fn sieve(n: u32) -> Vec<u32> {
-    let numbers = 
-    let mut primes = Vec::new();
+    primes_in_range(0, n as u64).map(|p| p as u32).collect()
+}
+
+/// Returns a lazy iterator over every prime, in increasing order.
+///
+/// Primes are produced one segment at a time, so memory use is bounded by
+/// the segment size plus the primes up to the square root of the largest
+/// value produced so far.
+pub fn primes() -> Primes {
+    primes_in_range(0, u64::MAX)
+}
+
+/// Returns a lazy iterator over the primes `p` with `lo <= p < hi`, in
+/// increasing order.
+pub fn primes_in_range(lo: u64, hi: u64) -> Primes {
+    Primes {
+        two: lo <= 2 && 2 < hi,
+        segments: Segments::new(lo, hi),
+        seg_lo: 0,
+        word_idx: 0,
+        word: 0,
+    }
+}
+
+/// Returns the number of primes less than `n`.
+///
+/// This sieves the whole range segment by segment but never materializes
+/// the primes, so it works for `n` up to about 10^10 in bounded memory.
+pub fn prime_count(n: u64) -> u64 {
+    let mut count = if n > 2 { 1 } else { 0 };
+    let mut segments = Segments::new(0, n);
+    while segments.advance().is_some() {
+        count += segments.words.iter()
+            .map(|w| w.count_ones() as u64)
+            .sum::<u64>();
+    }
+    count
+}
+
+/// Deterministic primality test for any `u64`, using Miller-Rabin with a
+/// fixed set of witnesses that is known to be exact below 2^64.
+pub fn is_prime(n: u64) -> bool {
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+    if n < 2 {
+        return false;
+    }
+    for &p in &WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+
+    'witness: for &a in &WITNESSES {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Iterator returned by `primes` and `primes_in_range`.
+pub struct Primes {
+    /// Whether 2 is in range and still has to be yielded.
+    two: bool,
+    segments: Segments,
+    /// First odd number covered by the current segment.
+    seg_lo: u64,
+    word_idx: usize,
+    /// Unconsumed candidate bits of `segments.words[word_idx]`.
+    word: u64,
+}
+
+impl Iterator for Primes {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.two {
+            self.two = false;
+            return Some(2);
+        }
+        loop {
+            if self.word != 0 {
+                let bit = self.word.trailing_zeros() as u64;
+                self.word &= self.word - 1;
+                return Some(self.seg_lo + 2 * (self.word_idx as u64 * 64 + bit));
+            }
+            self.word_idx += 1;
+            if self.word_idx < self.segments.words.len() {
+                self.word = self.segments.words[self.word_idx];
+                continue;
+            }
+            self.seg_lo = self.segments.advance()?;
+            self.word_idx = 0;
+            self.word = self.segments.words[0];
+        }
+    }
+}
 
-    for i in 2..n {
-        if !primes.contains(&i) {
-            for j in i*i..n {
-                if j % i != 0 {
-                    
-                }
+/// A segmented sieve over the odd numbers of `[lo, hi)`, with 2 left to the
+/// caller. Each call to `advance` sieves the next segment into `words`,
+/// where bit `i` is set when `seg_lo + 2 * i` is prime.
+struct Segments {
+    /// Next odd number to sieve.
+    lo: u64,
+    /// Exclusive upper bound of the whole range.
+    hi: u64,
+    /// Odd primes up to `base_limit`.
+    base: Vec<u64>,
+    base_limit: u64,
+    words: Vec<u64>,
+}
+
+impl Segments {
+    fn new(lo: u64, hi: u64) -> Self {
+        let lo = cmp::max(lo, 3) | 1;
+        Segments { lo, hi, base: Vec::new(), base_limit: 1, words: Vec::new() }
+    }
+
+    /// Sieves the next segment and returns the first odd number it covers,
+    /// or `None` once the range is exhausted.
+    fn advance(&mut self) -> Option<u64> {
+        if self.lo >= self.hi {
+            return None;
+        }
+        let seg_lo = self.lo;
+        let count = cmp::min(SEGMENT_ODDS, (self.hi - seg_lo).div_ceil(2));
+        let seg_hi = seg_lo.saturating_add(2 * count);
+        self.extend_base(seg_hi - 1);
+
+        let num_words = count.div_ceil(64) as usize;
+        self.words.clear();
+        self.words.resize(num_words, !0);
+        if !count.is_multiple_of(64) {
+            self.words[num_words - 1] = (1 << (count % 64)) - 1;
+        }
+
+        for &p in &self.base {
+            let square = match p.checked_mul(p) {
+                Some(square) if square < seg_hi => square,
+                _ => break,
+            };
+            // First odd multiple of p that is at least seg_lo, but never p
+            // itself: smaller multiples were crossed out by smaller primes.
+            let mut start = seg_lo.div_ceil(p) * p;
+            if start.is_multiple_of(2) {
+                start += p;
+            }
+            let mut i = (cmp::max(start, square) - seg_lo) / 2;
+            while i < count {
+                self.words[(i / 64) as usize] &= !(1 << (i % 64));
+                i += p;
             }
         }
+
+        self.lo = seg_hi;
+        Some(seg_lo)
+    }
+
+    /// Makes sure `base` holds every odd prime up to `sqrt(max)`.
+    fn extend_base(&mut self, max: u64) {
+        let needed = max.isqrt();
+        if needed <= self.base_limit {
+            return;
+        }
+        // Grow geometrically so an unbounded iterator re-sieves its base
+        // primes only logarithmically often, but never past sqrt(hi).
+        let limit = cmp::min(cmp::max(needed, 2 * self.base_limit),
+                             (self.hi - 1).isqrt());
+        self.base = small_odd_primes(limit as usize);
+        self.base_limit = limit;
+    }
+}
+
+/// Plain sieve of Eratosthenes for the odd primes up to and including
+/// `limit`, used to seed the segmented sieve.
+fn small_odd_primes(limit: usize) -> Vec<u64> {
+    let mut crossed = vec![false; limit + 1];
+    let mut primes = Vec::new();
+
+    for i in (3..limit + 1).step_by(2) {
+        if crossed[i] {
+            continue;
+        }
+        primes.push(i as u64);
+        for j in (i * i..limit + 1).step_by(2 * i) {
+            crossed[j] = true;
+        }
+    }
+    primes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trial_division(n: u64) -> bool {
+        n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d))
+    }
+
+    #[test]
+    fn test_sieve_matches_trial_division() {
+        for n in 0..300 {
+            let expected: Vec<u32> = (0..n).filter(|&i| trial_division(i as u64)).collect();
+            assert_eq!(sieve(n), expected, "sieve({})", n);
+        }
+    }
+
+    #[test]
+    fn test_segment_boundaries() {
+        // Ranges that start and end inside, and just around, segment and
+        // word boundaries.
+        let edge = 3 + 2 * SEGMENT_ODDS;
+        for &(lo, hi) in &[(edge - 130, edge + 130), (edge - 1, edge + 1),
+                           (2 * edge - 64, 2 * edge + 64), (0, 3), (3, 4), (8, 11)] {
+            let expected: Vec<u64> = (lo..hi).filter(|&i| trial_division(i)).collect();
+            assert_eq!(primes_in_range(lo, hi).collect::<Vec<_>>(), expected,
+                       "primes_in_range({}, {})", lo, hi);
+        }
+    }
+
+    #[test]
+    fn test_primes_in_range_large() {
+        let lo = 1_000_000_000_000;
+        let found: Vec<u64> = primes_in_range(lo, lo + 10_000).collect();
+        let expected: Vec<u64> = (lo..lo + 10_000).filter(|&i| is_prime(i)).collect();
+        assert_eq!(found, expected);
+        assert_eq!(found[0], 1_000_000_000_039);
+    }
+
+    #[test]
+    fn test_primes_iterator() {
+        let first: Vec<u64> = primes().take(10).collect();
+        assert_eq!(first, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        // The 100,000th prime.
+        assert_eq!(primes().nth(99_999), Some(1_299_709));
+    }
+
+    #[test]
+    fn test_is_prime() {
+        for n in 0..10_000 {
+            assert_eq!(is_prime(n), trial_division(n), "is_prime({})", n);
+        }
+        assert!(is_prime((1 << 61) - 1));
+        assert!(is_prime(18_446_744_073_709_551_557)); // largest u64 prime
+        assert!(!is_prime(3_215_031_751)); // strong pseudoprime to 2, 3, 5, 7
+        assert!(!is_prime(4_294_967_297)); // 2^32 + 1 = 641 * 6700417
+        assert!(!is_prime(u64::MAX));
+    }
+
+    #[test]
+    fn test_prime_count_known_values() {
+        assert_eq!(prime_count(0), 0);
+        assert_eq!(prime_count(3), 1);
+        assert_eq!(prime_count(10), 4);
+        assert_eq!(prime_count(1_000), 168);
+        assert_eq!(prime_count(1_000_000), 78_498);
+        assert_eq!(prime_count(10_000_000), 664_579);
+        assert_eq!(prime_count(100_000_000), 5_761_455);
+    }
+
+    #[test]
+    #[ignore] // takes a while in debug builds; run with `cargo test --release -- --ignored`
+    fn test_prime_count_ten_billion() {
+        assert_eq!(prime_count(10_000_000_000), 455_052_511);
     }
-    // TODO
-    unimplemented!();
 }
@@ -0,0 +1,387 @@
+// problem3.rs
+
+/// A move of the top disc of one peg onto another, as `(source, destination)`.
+/// Pegs are numbered from 1.
+pub type Move = (u8, u8);
+
+/// Solves for the sequence of moves required to move all discs from peg 1 to
+/// peg 3, using peg 2 as an intermediary.
+pub fn hanoi(num_discs: u32) -> Vec<Move> {
+    moves(num_discs).collect()
+}
+
+/// Returns a lazy iterator over the moves of `hanoi(num_discs)`, without
+/// allocating all 2^n - 1 of them up front.
+///
+/// Panics if `num_discs` is 64 or more, since such a solution could never be
+/// enumerated anyway.
+pub fn moves(num_discs: u32) -> Moves {
+    assert!(num_discs < 64, "too many discs: {}", num_discs);
+    Moves { next: 1, total: (1 << num_discs) - 1, odd: num_discs % 2 == 1 }
+}
+
+/// Iterator returned by `moves`.
+///
+/// Uses the binary solution: move number `m` (counting from 1) moves disc
+/// `m.trailing_zeros()` from peg `(m & (m - 1)) % 3` to peg
+/// `((m | (m - 1)) + 1) % 3`, which needs no stack at all.
+pub struct Moves {
+    next: u64,
+    total: u64,
+    /// The formula above ends on the middle peg for an odd number of
+    /// discs, so those solutions swap pegs 2 and 3.
+    odd: bool,
+}
+
+impl Iterator for Moves {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        if self.next > self.total {
+            return None;
+        }
+        let m = self.next;
+        self.next += 1;
+
+        let peg = |p: u64| -> u8 {
+            match (p % 3, self.odd) {
+                (0, _) => 1,
+                (1, false) | (2, true) => 3,
+                _ => 2,
+            }
+        };
+        Some((peg(m & (m - 1)), peg((m | (m - 1)) + 1)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = (self.total + 1 - self.next) as usize;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for Moves {}
+
+/// Solves the puzzle with `num_pegs` pegs using the Frame-Stewart algorithm,
+/// moving all discs from peg 1 to peg `num_pegs`.
+///
+/// With three pegs this is the classic solution. With more, the top `t`
+/// discs are parked on a spare peg using all pegs, the rest are moved with
+/// one peg fewer, and the `t` discs are moved back on top, where `t` is
+/// chosen to minimise the number of moves.
+///
+/// The result holds `frame_stewart_count(num_discs, num_pegs)` moves; check
+/// that first when the input isn't trusted.
+///
+/// Panics if `num_pegs` is less than 3 (unless there are no discs).
+pub fn hanoi_pegs(num_discs: u32, num_pegs: u8) -> Vec<Move> {
+    if num_discs == 0 {
+        return Vec::new();
+    }
+    assert!(num_pegs >= 3, "need at least 3 pegs, got {}", num_pegs);
+
+    let mut result = Vec::new();
+    let mut pegs: Vec<u8> = (1..=num_pegs).collect();
+    let dst = pegs.pop().unwrap();
+    let src = pegs.remove(0);
+    frame_stewart(num_discs as usize, src, dst, &pegs, &mut result);
+    result
+}
+
+/// The number of moves `hanoi_pegs(num_discs, num_pegs)` makes, saturating
+/// at `u128::MAX`. Takes time proportional to the logarithm of the answer,
+/// so it is cheap even when the moves themselves could never be listed.
+///
+/// Panics if `num_pegs` is less than 3 (unless there are no discs).
+pub fn frame_stewart_count(num_discs: u32, num_pegs: u8) -> u128 {
+    if num_discs == 0 {
+        return 0;
+    }
+    assert!(num_pegs >= 3, "need at least 3 pegs, got {}", num_pegs);
+    frame_stewart_cost(num_discs as usize, num_pegs as usize)
+}
+
+/// Moves needed for `n` discs and `k >= 3` pegs, saturating.
+///
+/// Adding a disc costs a power of two more moves: with `k` pegs, the
+/// `C(j + k - 3, k - 3)` discs after the first `C(j + k - 3, k - 2)` each
+/// cost `2^j`.
+fn frame_stewart_cost(mut n: usize, k: usize) -> u128 {
+    let mut total = 0u128;
+    let mut block = 1u128;
+    let mut j = 0;
+    while n > 0 {
+        if j >= 128 {
+            return u128::MAX;
+        }
+        let discs = block.min(n as u128);
+        total = total.saturating_add(discs.saturating_mul(1 << j));
+        n -= discs as usize;
+        j += 1;
+        // C(j + k - 3, k - 3) from C(j + k - 4, k - 3); too big to matter
+        // once it overflows.
+        block = block.checked_mul((j + k - 3) as u128).map_or(u128::MAX, |b| b / j as u128);
+    }
+    total
+}
+
+/// The number of discs to park when moving `n >= 2` discs with `k` pegs:
+/// the first `t` minimising `2 cost(t, k) + cost(n - t, k - 1)`. Both terms
+/// are convex in `t`, so a binary search on the slope finds it.
+fn frame_stewart_split(n: usize, k: usize) -> usize {
+    if k == 3 {
+        return n - 1;
+    }
+    let cost = |t| {
+        frame_stewart_cost(t, k).saturating_mul(2).saturating_add(frame_stewart_cost(n - t, k - 1))
+    };
+    let (mut lo, mut hi) = (0, n - 1);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if cost(mid + 1) >= cost(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    lo
+}
+
+/// Moves `n` discs from `src` to `dst`, with `spare` listing the other pegs
+/// that may be used.
+fn frame_stewart(n: usize, src: u8, dst: u8, spare: &[u8], result: &mut Vec<Move>) {
+    if n == 0 {
+        return;
+    }
+    if n == 1 {
+        result.push((src, dst));
+        return;
+    }
+    let t = frame_stewart_split(n, spare.len() + 2);
+
+    // Park the top t discs on the first spare peg, using every peg.
+    let park = spare[0];
+    let mut others: Vec<u8> = spare[1..].to_vec();
+    others.push(dst);
+    frame_stewart(t, src, park, &others, result);
+
+    // Move the rest without touching the parked peg.
+    frame_stewart(n - t, src, dst, &spare[1..], result);
+
+    // Bring the parked discs back on top.
+    others.pop();
+    others.push(src);
+    frame_stewart(t, park, dst, &others, result);
+}
+
+/// Ways a move can break the rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IllegalMove {
+    /// The move names a peg that does not exist.
+    NoSuchPeg(u8),
+    /// The move names the same peg as source and destination.
+    SamePeg(u8),
+    /// There is no disc on the source peg.
+    EmptySource(u8),
+    /// The moved disc is larger than the disc on top of the destination.
+    LargerOnSmaller { disc: u32, onto: u32 },
+}
+
+/// A simulated puzzle, used to check move sequences. Discs are numbered by
+/// size from 1 (smallest) to `num_discs`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HanoiState {
+    /// Bottom to top.
+    pegs: Vec<Vec<u32>>,
+}
+
+impl HanoiState {
+    /// Creates a puzzle with `num_pegs` pegs and all discs on peg 1.
+    pub fn new(num_discs: u32, num_pegs: u8) -> Self {
+        let mut pegs = vec![Vec::new(); num_pegs as usize];
+        if let Some(first) = pegs.first_mut() {
+            first.extend((1..=num_discs).rev());
+        }
+        HanoiState { pegs }
+    }
+
+    /// The discs on `peg`, from bottom to top.
+    pub fn peg(&self, peg: u8) -> Option<&[u32]> {
+        self.index(peg).ok().map(|i| &self.pegs[i][..])
+    }
+
+    /// Whether every disc is on the last peg.
+    pub fn is_solved(&self) -> bool {
+        self.pegs.iter().rev().skip(1).all(|p| p.is_empty())
+    }
+
+    /// Applies one move, leaving the state untouched if it is illegal.
+    pub fn apply(&mut self, (from, to): Move) -> Result<(), IllegalMove> {
+        let src = self.index(from)?;
+        let dst = self.index(to)?;
+        if src == dst {
+            return Err(IllegalMove::SamePeg(from));
+        }
+        let disc = *self.pegs[src].last().ok_or(IllegalMove::EmptySource(from))?;
+        if let Some(&onto) = self.pegs[dst].last() {
+            if onto < disc {
+                return Err(IllegalMove::LargerOnSmaller { disc, onto });
+            }
+        }
+        self.pegs[src].pop();
+        self.pegs[dst].push(disc);
+        Ok(())
+    }
+
+    /// Applies `moves` in order. On the first illegal move, stops and returns
+    /// its index along with what was wrong with it.
+    pub fn replay<I>(&mut self, moves: I) -> Result<(), (usize, IllegalMove)>
+        where I: IntoIterator<Item = Move>
+    {
+        for (i, m) in moves.into_iter().enumerate() {
+            self.apply(m).map_err(|e| (i, e))?;
+        }
+        Ok(())
+    }
+
+    fn index(&self, peg: u8) -> Result<usize, IllegalMove> {
+        if peg >= 1 && (peg as usize) <= self.pegs.len() {
+            Ok(peg as usize - 1)
+        } else {
+            Err(IllegalMove::NoSuchPeg(peg))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn recursive(n: u32, src: u8, dst: u8, via: u8, out: &mut Vec<Move>) {
+        if n > 0 {
+            recursive(n - 1, src, via, dst, out);
+            out.push((src, dst));
+            recursive(n - 1, via, dst, src, out);
+        }
+    }
+
+    #[test]
+    fn test_hanoi_small() {
+        assert_eq!(hanoi(0), vec![]);
+        assert_eq!(hanoi(1), vec![(1, 3)]);
+        assert_eq!(hanoi(2), vec![(1, 2), (1, 3), (2, 3)]);
+        assert_eq!(hanoi(3), vec![(1, 3), (1, 2), (3, 2), (1, 3), (2, 1), (2, 3), (1, 3)]);
+    }
+
+    #[test]
+    fn test_moves_match_recursive_solution() {
+        for n in 0..15 {
+            let mut expected = Vec::new();
+            recursive(n, 1, 3, 2, &mut expected);
+            assert_eq!(hanoi(n), expected, "{} discs", n);
+        }
+    }
+
+    #[test]
+    fn test_moves_is_lazy() {
+        let it = moves(63);
+        assert_eq!(it.len(), (1u64 << 63) as usize - 1);
+        // With the same parity, the top three discs start out exactly like
+        // the three-disc puzzle.
+        assert_eq!(it.take(7).collect::<Vec<_>>(), hanoi(3));
+    }
+
+    #[test]
+    fn test_replay_solutions() {
+        for n in 0..12 {
+            let mut state = HanoiState::new(n, 3);
+            assert_eq!(state.replay(moves(n)), Ok(()));
+            assert!(state.is_solved());
+        }
+    }
+
+    #[test]
+    fn test_frame_stewart_counts() {
+        // Known minimal move counts for four pegs.
+        let expected = [0, 1, 3, 5, 9, 13, 17, 25, 33, 41, 49];
+        for (n, &count) in expected.iter().enumerate() {
+            assert_eq!(hanoi_pegs(n as u32, 4).len(), count, "{} discs", n);
+        }
+        assert_eq!(hanoi_pegs(10, 3), hanoi(10));
+        assert_eq!(hanoi_pegs(20, 5).len(), 111);
+        // The three-peg costs behind this overflow u128, so they saturate.
+        assert_eq!(hanoi_pegs(140, 4).len(), 1_245_185);
+        // With a spare peg per disc, each disc but the largest moves twice.
+        let moves = hanoi_pegs(3, 255);
+        assert_eq!(moves.len(), 5);
+        assert_eq!(HanoiState::new(3, 255).replay(moves), Ok(()));
+    }
+
+    #[test]
+    fn test_frame_stewart_matches_dynamic_programming() {
+        // cost[k][n] = min over t of 2 cost[k][t] + cost[k - 1][n - t].
+        let (max_discs, max_pegs) = (60, 8);
+        let mut cost = vec![vec![0u128; max_discs + 1]; max_pegs + 1];
+        for n in 1..=max_discs {
+            cost[3][n] = 2 * cost[3][n - 1] + 1;
+        }
+        for k in 4..=max_pegs {
+            for n in 1..=max_discs {
+                let (t, best) = (0..n)
+                    .map(|t| (t, 2 * cost[k][t] + cost[k - 1][n - t]))
+                    .min_by_key(|&(_, c)| c)
+                    .unwrap();
+                cost[k][n] = best;
+                if n >= 2 {
+                    assert_eq!(frame_stewart_split(n, k), t, "{} discs, {} pegs", n, k);
+                }
+            }
+        }
+        for (k, row) in cost.iter().enumerate().skip(3) {
+            for (n, &count) in row.iter().enumerate() {
+                assert_eq!(frame_stewart_count(n as u32, k as u8), count);
+            }
+        }
+        for &(n, k) in &[(0, 3), (7, 3), (25, 4), (40, 6), (300, 255)] {
+            assert_eq!(hanoi_pegs(n, k).len() as u128, frame_stewart_count(n, k));
+        }
+    }
+
+    #[test]
+    fn test_frame_stewart_count_saturates() {
+        assert_eq!(frame_stewart_count(127, 3), u128::MAX >> 1);
+        assert_eq!(frame_stewart_count(200, 3), u128::MAX);
+        assert_eq!(frame_stewart_count(u32::MAX, 4), u128::MAX);
+        assert!(frame_stewart_count(u32::MAX, 255) > 1 << 32);
+    }
+
+    #[test]
+    fn test_frame_stewart_is_legal() {
+        for pegs in 3..7 {
+            for n in 0..14 {
+                let mut state = HanoiState::new(n, pegs);
+                assert_eq!(state.replay(hanoi_pegs(n, pegs)), Ok(()));
+                assert!(state.is_solved(), "{} discs, {} pegs", n, pegs);
+            }
+        }
+    }
+
+    #[test]
+    fn test_illegal_moves() {
+        let mut state = HanoiState::new(3, 3);
+        assert_eq!(state.apply((2, 3)), Err(IllegalMove::EmptySource(2)));
+        assert_eq!(state.apply((1, 4)), Err(IllegalMove::NoSuchPeg(4)));
+        assert_eq!(state.apply((0, 1)), Err(IllegalMove::NoSuchPeg(0)));
+        assert_eq!(state.apply((1, 1)), Err(IllegalMove::SamePeg(1)));
+        assert_eq!(state.peg(1), Some(&[3, 2, 1][..]));
+
+        let bad = vec![(1, 3), (1, 2), (1, 3)];
+        let mut state = HanoiState::new(3, 3);
+        assert_eq!(state.replay(bad),
+                   Err((2, IllegalMove::LargerOnSmaller { disc: 3, onto: 1 })));
+        // Everything before the illegal move was applied.
+        assert_eq!(state.peg(1), Some(&[3][..]));
+        assert_eq!(state.peg(2), Some(&[2][..]));
+        assert_eq!(state.peg(3), Some(&[1][..]));
+        assert!(!state.is_solved());
+    }
+}
@@ -0,0 +1,1162 @@
+// problem4.rs
+
+use std::error::Error;
+use std::f64::consts::LN_2;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use hash::{self, Djb2Hasher, Fnv1aHasher64, Murmur3Hasher, SipHasher24, XxHasher64};
+
+/// djb2 as given in the README, which shifts right where the classic
+/// algorithm shifts left. Kept for compatibility; see `hash::djb2`.
+pub fn djb2(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 5381;
+    for b in bytes {
+        // hash * 33 + c
+        hash = hash.wrapping_shr(5).wrapping_add(hash).wrapping_add(*b as u64);
+    }
+    hash
+}
+
+/// 64-bit FNV-1a, the same as `hash::fnv1a_64`.
+pub fn fnv(bytes: &[u8]) -> u64 {
+    hash::fnv1a_64(bytes)
+}
+
+pub fn jenkins(bytes: &[u8]) -> u64 {
+    let mut hasher = JenkinsHasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Simulates a bloom filter by accepting an array of three hash functions, a
+/// data vector, and another value to query. Returns `true` if `value` is
+/// "probably" in the data vector and `false` if it is definitely not in the
+/// data vector.
+#[allow(clippy::ptr_arg)]
+pub fn bloom(data: &Vec<&str>, hashes: [fn(&[u8]) -> u64; 3], value: &str)
+        -> bool {
+    let mut filter = [false; 20];
+
+    for item in data {
+        for hash in &hashes {
+            filter[(hash(item.as_bytes()) % 20) as usize] = true;
+        }
+    }
+    hashes.iter().all(|hash| filter[(hash(value.as_bytes()) % 20) as usize])
+}
+
+//
+// Hashers
+//
+
+/// `jenkins` as a streaming `Hasher`. The final avalanche is applied in
+/// `finish`, so more bytes can still be written afterwards.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JenkinsHasher(u64);
+
+impl Hasher for JenkinsHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = self.0.wrapping_add(*b as u64);
+            self.0 = self.0.wrapping_add(self.0.wrapping_shr(10));
+            self.0 ^= self.0.wrapping_shl(6);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = self.0;
+        hash = hash.wrapping_add(hash.wrapping_shr(3));
+        hash ^= hash.wrapping_shl(11);
+        hash = hash.wrapping_add(hash.wrapping_shr(15));
+        hash
+    }
+}
+
+/// A hash function for Bloom filters, chosen at runtime: djb2, FNV or
+/// `jenkins`, or one of the better-mixing functions from the `hash` module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashKind {
+    /// The classic djb2, `hash::djb2`, which shifts left; not the README
+    /// variant `djb2` above.
+    Djb2,
+    /// 64-bit FNV-1a, as `fnv`.
+    Fnv,
+    Jenkins,
+    /// MurmurHash3 x86_32. Only 32 bits, so best as the second hash of a
+    /// pair.
+    Murmur3,
+    XxHash64,
+    /// SipHash-2-4 with an all-zero key.
+    SipHash,
+}
+
+impl HashKind {
+    /// Hashes `bytes` with this function.
+    pub fn hash_bytes(self, bytes: &[u8]) -> u64 {
+        let mut hasher = self.hasher();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    /// Hashes any `Hash` value with this function.
+    pub fn hash<T: Hash + ?Sized>(self, item: &T) -> u64 {
+        let mut hasher = self.hasher();
+        item.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// A fresh streaming hasher for this function.
+    pub fn hasher(self) -> KindHasher {
+        match self {
+            HashKind::Djb2 => KindHasher::Djb2(Djb2Hasher::default()),
+            HashKind::Fnv => KindHasher::Fnv(Fnv1aHasher64::default()),
+            HashKind::Jenkins => KindHasher::Jenkins(JenkinsHasher::default()),
+            HashKind::Murmur3 => KindHasher::Murmur3(Murmur3Hasher::default()),
+            HashKind::XxHash64 => KindHasher::XxHash64(XxHasher64::default()),
+            HashKind::SipHash => KindHasher::SipHash(SipHasher24::default()),
+        }
+    }
+
+    /// Identifier used for this function in serialized filters.
+    pub fn id(self) -> u8 {
+        match self {
+            HashKind::Djb2 => 1,
+            HashKind::Fnv => 2,
+            HashKind::Jenkins => 3,
+            HashKind::Murmur3 => 4,
+            HashKind::XxHash64 => 5,
+            HashKind::SipHash => 6,
+        }
+    }
+
+    /// The function with identifier `id`, if there is one.
+    pub fn from_id(id: u8) -> Option<HashKind> {
+        match id {
+            1 => Some(HashKind::Djb2),
+            2 => Some(HashKind::Fnv),
+            3 => Some(HashKind::Jenkins),
+            4 => Some(HashKind::Murmur3),
+            5 => Some(HashKind::XxHash64),
+            6 => Some(HashKind::SipHash),
+            _ => None,
+        }
+    }
+}
+
+/// Hasher returned by `HashKind::hasher`.
+///
+/// Integers are fed to the hash function in little-endian order, and
+/// `usize`/`isize` as 64-bit values, so the hash of a value (and therefore
+/// its position in a serialized filter) is the same on every platform.
+#[derive(Clone, Copy, Debug)]
+pub enum KindHasher {
+    Djb2(Djb2Hasher),
+    Fnv(Fnv1aHasher64),
+    Jenkins(JenkinsHasher),
+    Murmur3(Murmur3Hasher),
+    XxHash64(XxHasher64),
+    SipHash(SipHasher24),
+}
+
+impl Hasher for KindHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        match *self {
+            KindHasher::Djb2(ref mut h) => h.write(bytes),
+            KindHasher::Fnv(ref mut h) => h.write(bytes),
+            KindHasher::Jenkins(ref mut h) => h.write(bytes),
+            KindHasher::Murmur3(ref mut h) => h.write(bytes),
+            KindHasher::XxHash64(ref mut h) => h.write(bytes),
+            KindHasher::SipHash(ref mut h) => h.write(bytes),
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        match *self {
+            KindHasher::Djb2(ref h) => h.finish(),
+            KindHasher::Fnv(ref h) => h.finish(),
+            KindHasher::Jenkins(ref h) => h.finish(),
+            KindHasher::Murmur3(ref h) => h.finish(),
+            KindHasher::XxHash64(ref h) => h.finish(),
+            KindHasher::SipHash(ref h) => h.finish(),
+        }
+    }
+
+    fn write_u16(&mut self, n: u16) { self.write(&n.to_le_bytes()) }
+    fn write_u32(&mut self, n: u32) { self.write(&n.to_le_bytes()) }
+    fn write_u64(&mut self, n: u64) { self.write(&n.to_le_bytes()) }
+    fn write_u128(&mut self, n: u128) { self.write(&n.to_le_bytes()) }
+    fn write_usize(&mut self, n: usize) { self.write_u64(n as u64) }
+}
+
+/// Yields the `k` bit positions of `item` in a filter of `num_bits` bits.
+///
+/// Rather than running `k` independent hash functions, the positions are
+/// derived from two base hashes as `h1 + i * h2` (Kirsch and Mitzenmacher),
+/// which gives the same asymptotic false positive rate.
+fn bit_indices<T: Hash + ?Sized>(item: &T, hashes: [HashKind; 2], num_bits: u64,
+                                  k: u32) -> impl Iterator<Item = u64> {
+    let h1 = hashes[0].hash(item);
+    // An odd step never cycles early when `num_bits` is a power of two.
+    let h2 = hashes[1].hash(item) | 1;
+    (0..k as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+}
+
+//
+// Bloom filter
+//
+
+/// The hash pair used by filters that don't ask for a specific one.
+pub const DEFAULT_HASHES: [HashKind; 2] = [HashKind::Fnv, HashKind::Jenkins];
+
+/// A Bloom filter over values of type `T`, stored as a packed bit array.
+///
+/// `T` may be unsized, so `BloomFilter<str>` can be queried with `&str`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BloomFilter<T: ?Sized> {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    hashes: [HashKind; 2],
+    marker: PhantomData<fn(&T)>,
+}
+
+/// Returned when combining two filters that differ in size, number of
+/// hashes or hash functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncompatibleFilters;
+
+impl fmt::Display for IncompatibleFilters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bloom filters have different shapes")
+    }
+}
+
+impl Error for IncompatibleFilters {}
+
+impl<T: Hash + ?Sized> BloomFilter<T> {
+    /// Creates an empty filter with `num_bits` bits and `num_hashes` bit
+    /// positions per item, using `DEFAULT_HASHES`.
+    ///
+    /// Panics if either is zero.
+    pub fn new(num_bits: u64, num_hashes: u32) -> Self {
+        BloomFilter::with_hashes(num_bits, num_hashes, DEFAULT_HASHES)
+    }
+
+    /// Like `new`, but deriving bit positions from the given pair of hash
+    /// functions.
+    pub fn with_hashes(num_bits: u64, num_hashes: u32, hashes: [HashKind; 2]) -> Self {
+        assert!(num_bits > 0, "a bloom filter needs at least one bit");
+        assert!(num_hashes > 0, "a bloom filter needs at least one hash");
+        BloomFilter {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes,
+            hashes,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates a filter sized to hold `expected_items` items with a false
+    /// positive rate of about `fp_rate`.
+    ///
+    /// Panics unless `0 < fp_rate < 1`.
+    pub fn with_rate(expected_items: u64, fp_rate: f64) -> Self {
+        let num_bits = optimal_num_bits(expected_items, fp_rate);
+        BloomFilter::new(num_bits, optimal_num_hashes(num_bits, expected_items))
+    }
+
+    /// Adds `item` to the set. Returns `false` if it was (probably) already
+    /// there.
+    pub fn insert(&mut self, item: &T) -> bool {
+        let mut added = false;
+        for i in bit_indices(item, self.hashes, self.num_bits, self.num_hashes) {
+            let (word, mask) = ((i / 64) as usize, 1 << (i % 64));
+            added |= self.bits[word] & mask == 0;
+            self.bits[word] |= mask;
+        }
+        added
+    }
+
+    /// Returns `false` if `item` is definitely not in the set, and `true`
+    /// if it probably is.
+    pub fn contains(&self, item: &T) -> bool {
+        bit_indices(item, self.hashes, self.num_bits, self.num_hashes)
+            .all(|i| self.bits[(i / 64) as usize] & (1 << (i % 64)) != 0)
+    }
+
+    /// Adds every item of `other` to this filter.
+    pub fn union(&mut self, other: &Self) -> Result<(), IncompatibleFilters> {
+        self.check_shape(other)?;
+        for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+            *a |= *b;
+        }
+        Ok(())
+    }
+
+    /// Keeps only the bits set in both filters. Items in both sets are still
+    /// reported as present, but the false positive rate of the result is
+    /// higher than that of a filter built from the intersection directly.
+    pub fn intersect(&mut self, other: &Self) -> Result<(), IncompatibleFilters> {
+        self.check_shape(other)?;
+        for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+            *a &= *b;
+        }
+        Ok(())
+    }
+
+    fn check_shape(&self, other: &Self) -> Result<(), IncompatibleFilters> {
+        if self.num_bits == other.num_bits && self.num_hashes == other.num_hashes
+                && self.hashes == other.hashes {
+            Ok(())
+        } else {
+            Err(IncompatibleFilters)
+        }
+    }
+}
+
+impl<T: ?Sized> BloomFilter<T> {
+    /// Total number of bits.
+    pub fn num_bits(&self) -> u64 { self.num_bits }
+
+    /// Number of bit positions set per item.
+    pub fn num_hashes(&self) -> u32 { self.num_hashes }
+
+    /// The pair of hash functions bit positions are derived from.
+    pub fn hashes(&self) -> [HashKind; 2] { self.hashes }
+
+    /// Number of bits currently set.
+    pub fn count_ones(&self) -> u64 {
+        self.bits.iter().map(|w| w.count_ones() as u64).sum()
+    }
+
+    /// Whether nothing has been inserted (or every insert was cleared).
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&w| w == 0)
+    }
+
+    /// Removes every item.
+    pub fn clear(&mut self) {
+        for w in &mut self.bits {
+            *w = 0;
+        }
+    }
+
+    /// Estimates how many distinct items have been inserted, from the
+    /// fraction of bits that are set (Swamidass and Baldi). Returns infinity
+    /// once every bit is set.
+    pub fn estimated_len(&self) -> f64 {
+        let m = self.num_bits as f64;
+        let k = self.num_hashes as f64;
+        -(m / k) * (1.0 - self.count_ones() as f64 / m).ln()
+    }
+
+    /// The probability that `contains` returns `true` for an item that was
+    /// never inserted, given the bits set so far.
+    pub fn false_positive_rate(&self) -> f64 {
+        (self.count_ones() as f64 / self.num_bits as f64).powi(self.num_hashes as i32)
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for BloomFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BloomFilter")
+            .field("num_bits", &self.num_bits)
+            .field("num_hashes", &self.num_hashes)
+            .field("hashes", &self.hashes)
+            .field("ones", &self.count_ones())
+            .finish()
+    }
+}
+
+//
+// Serialization
+//
+
+/// Magic bytes at the start of a serialized `BloomFilter`.
+pub const MAGIC: [u8; 4] = *b"BLMF";
+
+/// Current version of the serialized format.
+pub const FORMAT_VERSION: u16 = 1;
+
+/// An error reading a serialized `BloomFilter`.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// The input ended before the end of the filter.
+    Truncated,
+    /// The input does not start with `MAGIC`.
+    BadMagic,
+    /// The input was written by an unknown version of the format.
+    UnsupportedVersion(u16),
+    /// The header names a hash function that doesn't exist.
+    UnknownHash(u8),
+    /// The header gives zero bits or zero hashes.
+    InvalidHeader,
+    /// Bits are set past the end of the filter.
+    StrayBits,
+    /// The stored checksum doesn't match the contents.
+    ChecksumMismatch { expected: u64, found: u64 },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::Io(ref e) => write!(f, "i/o error: {}", e),
+            DecodeError::Truncated => write!(f, "bloom filter is truncated"),
+            DecodeError::BadMagic => write!(f, "not a bloom filter"),
+            DecodeError::UnsupportedVersion(v) => {
+                write!(f, "unsupported bloom filter format version {}", v)
+            }
+            DecodeError::UnknownHash(id) => write!(f, "unknown hash function id {}", id),
+            DecodeError::InvalidHeader => write!(f, "bloom filter has zero bits or hashes"),
+            DecodeError::StrayBits => write!(f, "bits set past the end of the bloom filter"),
+            DecodeError::ChecksumMismatch { expected, found } => {
+                write!(f, "checksum mismatch: expected {:016x}, found {:016x}",
+                       expected, found)
+            }
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            DecodeError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            DecodeError::Truncated
+        } else {
+            DecodeError::Io(e)
+        }
+    }
+}
+
+/// Passes writes through to `inner`, hashing every byte with `fnv`.
+struct ChecksumWriter<W> {
+    inner: W,
+    hasher: Fnv1aHasher64,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.hasher.write(bytes);
+        self.inner.write_all(bytes)
+    }
+}
+
+/// Passes reads through from `inner`, hashing every byte with `fnv`.
+struct ChecksumReader<R> {
+    inner: R,
+    hasher: Fnv1aHasher64,
+}
+
+impl<R: Read> ChecksumReader<R> {
+    fn read_bytes<A: AsMut<[u8]> + Default>(&mut self) -> io::Result<A> {
+        let mut buf = A::default();
+        self.inner.read_exact(buf.as_mut())?;
+        self.hasher.write(buf.as_mut());
+        Ok(buf)
+    }
+}
+
+impl<T: Hash + ?Sized> BloomFilter<T> {
+    /// Writes the filter to `writer` in a versioned format that is the same
+    /// on every platform. All integers are little-endian:
+    ///
+    /// | bytes | contents                                           |
+    /// |-------|----------------------------------------------------|
+    /// | 4     | `MAGIC`                                            |
+    /// | 2     | `FORMAT_VERSION`                                   |
+    /// | 1 + 1 | ids of the two hash functions (`HashKind::id`)     |
+    /// | 4     | number of hashes                                   |
+    /// | 8     | number of bits, `m`                                |
+    /// | 8 * ⌈m / 64⌉ | the bits, as 64-bit words, bit `i` in word `i / 64` |
+    /// | 8     | `fnv` of everything above                          |
+    pub fn write_to<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut w = ChecksumWriter { inner: writer, hasher: Fnv1aHasher64::default() };
+        w.write_bytes(&MAGIC)?;
+        w.write_bytes(&FORMAT_VERSION.to_le_bytes())?;
+        w.write_bytes(&[self.hashes[0].id(), self.hashes[1].id()])?;
+        w.write_bytes(&self.num_hashes.to_le_bytes())?;
+        w.write_bytes(&self.num_bits.to_le_bytes())?;
+        for word in &self.bits {
+            w.write_bytes(&word.to_le_bytes())?;
+        }
+        let checksum = w.hasher.finish();
+        w.inner.write_all(&checksum.to_le_bytes())
+    }
+
+    /// Reads a filter written by `write_to`.
+    ///
+    /// The bits are read incrementally, so a corrupted size in the header
+    /// results in `Truncated` rather than a huge allocation.
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, DecodeError> {
+        let mut r = ChecksumReader { inner: reader, hasher: Fnv1aHasher64::default() };
+        if r.read_bytes::<[u8; 4]>()? != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        let version = u16::from_le_bytes(r.read_bytes()?);
+        if version != FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let ids: [u8; 2] = r.read_bytes()?;
+        let num_hashes = u32::from_le_bytes(r.read_bytes()?);
+        let num_bits = u64::from_le_bytes(r.read_bytes()?);
+
+        let mut bits = Vec::new();
+        for _ in 0..num_bits.div_ceil(64) {
+            bits.push(u64::from_le_bytes(r.read_bytes()?));
+        }
+
+        let found = r.hasher.finish();
+        let mut stored = [0; 8];
+        r.inner.read_exact(&mut stored)?;
+        let expected = u64::from_le_bytes(stored);
+        if expected != found {
+            return Err(DecodeError::ChecksumMismatch { expected, found });
+        }
+
+        let mut hashes = [HashKind::Fnv; 2];
+        for (hash, &id) in hashes.iter_mut().zip(&ids) {
+            *hash = HashKind::from_id(id).ok_or(DecodeError::UnknownHash(id))?;
+        }
+        if num_bits == 0 || num_hashes == 0 {
+            return Err(DecodeError::InvalidHeader);
+        }
+        if num_bits % 64 != 0 && bits[bits.len() - 1] >> (num_bits % 64) != 0 {
+            return Err(DecodeError::StrayBits);
+        }
+
+        Ok(BloomFilter { bits, num_bits, num_hashes, hashes, marker: PhantomData })
+    }
+}
+
+//
+// Counting Bloom filter
+//
+
+/// Largest value a counter can hold. Counters that reach it stay there.
+const COUNTER_MAX: u8 = 15;
+
+/// A Bloom filter that supports removal, by keeping a small counter instead
+/// of a single bit in each slot.
+///
+/// Counters are 4 bits wide, packed two to a byte, and saturate at 15: a
+/// saturated counter is never decremented again, because its true count is
+/// no longer known. That can only cause false positives, never false
+/// negatives.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CountingBloomFilter<T: ?Sized> {
+    counters: Vec<u8>,
+    num_counters: u64,
+    num_hashes: u32,
+    hashes: [HashKind; 2],
+    marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Hash + ?Sized> CountingBloomFilter<T> {
+    /// Creates an empty filter with `num_counters` slots and `num_hashes`
+    /// slots per item, using `DEFAULT_HASHES`.
+    ///
+    /// Panics if either is zero.
+    pub fn new(num_counters: u64, num_hashes: u32) -> Self {
+        CountingBloomFilter::with_hashes(num_counters, num_hashes, DEFAULT_HASHES)
+    }
+
+    /// Like `new`, but deriving slots from the given pair of hash functions.
+    pub fn with_hashes(num_counters: u64, num_hashes: u32, hashes: [HashKind; 2]) -> Self {
+        assert!(num_counters > 0, "a bloom filter needs at least one counter");
+        assert!(num_hashes > 0, "a bloom filter needs at least one hash");
+        CountingBloomFilter {
+            counters: vec![0; num_counters.div_ceil(2) as usize],
+            num_counters,
+            num_hashes,
+            hashes,
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates a filter sized to hold `expected_items` items with a false
+    /// positive rate of about `fp_rate`.
+    pub fn with_rate(expected_items: u64, fp_rate: f64) -> Self {
+        let num_counters = optimal_num_bits(expected_items, fp_rate);
+        CountingBloomFilter::new(num_counters,
+                                 optimal_num_hashes(num_counters, expected_items))
+    }
+
+    /// Adds `item` to the multiset.
+    pub fn insert(&mut self, item: &T) {
+        for i in bit_indices(item, self.hashes, self.num_counters, self.num_hashes) {
+            let c = self.counter(i);
+            if c < COUNTER_MAX {
+                self.set_counter(i, c + 1);
+            }
+        }
+    }
+
+    /// Returns `false` if `item` is definitely not in the multiset, and
+    /// `true` if it probably is.
+    pub fn contains(&self, item: &T) -> bool {
+        bit_indices(item, self.hashes, self.num_counters, self.num_hashes)
+            .all(|i| self.counter(i) > 0)
+    }
+
+    /// Removes one occurrence of `item`. Returns `false`, and changes
+    /// nothing, if `item` was definitely not present.
+    ///
+    /// Only remove items that were actually inserted: removing a false
+    /// positive decrements counters that belong to other items, and can
+    /// make them disappear. Counters that reach 0 that way stay at 0.
+    pub fn remove(&mut self, item: &T) -> bool {
+        if !self.contains(item) {
+            return false;
+        }
+        for i in bit_indices(item, self.hashes, self.num_counters, self.num_hashes) {
+            let c = self.counter(i);
+            // A false positive can hit a slot twice after its counter has
+            // already dropped to 0.
+            if c > 0 && c < COUNTER_MAX {
+                self.set_counter(i, c - 1);
+            }
+        }
+        true
+    }
+
+    fn counter(&self, i: u64) -> u8 {
+        let byte = self.counters[(i / 2) as usize];
+        if i.is_multiple_of(2) { byte & 0x0f } else { byte >> 4 }
+    }
+
+    fn set_counter(&mut self, i: u64, value: u8) {
+        let byte = &mut self.counters[(i / 2) as usize];
+        if i.is_multiple_of(2) {
+            *byte = (*byte & 0xf0) | value;
+        } else {
+            *byte = (*byte & 0x0f) | (value << 4);
+        }
+    }
+}
+
+impl<T: ?Sized> CountingBloomFilter<T> {
+    /// Total number of counters.
+    pub fn num_counters(&self) -> u64 { self.num_counters }
+
+    /// Number of counters incremented per item.
+    pub fn num_hashes(&self) -> u32 { self.num_hashes }
+
+    /// The pair of hash functions slots are derived from.
+    pub fn hashes(&self) -> [HashKind; 2] { self.hashes }
+
+    /// Whether every counter is zero.
+    pub fn is_empty(&self) -> bool {
+        self.counters.iter().all(|&c| c == 0)
+    }
+
+    /// Removes every item.
+    pub fn clear(&mut self) {
+        for c in &mut self.counters {
+            *c = 0;
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for CountingBloomFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CountingBloomFilter")
+            .field("num_counters", &self.num_counters)
+            .field("num_hashes", &self.num_hashes)
+            .field("hashes", &self.hashes)
+            .finish()
+    }
+}
+
+//
+// Scalable Bloom filter
+//
+
+/// Each new sub-filter holds this many times more items than the last.
+const GROWTH: u64 = 2;
+
+/// Each new sub-filter's false positive rate is this fraction of the last.
+const TIGHTENING: f64 = 0.5;
+
+/// A Bloom filter that grows as items are added, so it needs no capacity up
+/// front (Almeida et al., "Scalable Bloom Filters").
+///
+/// Items go into the newest of a chain of plain filters. When it is full, a
+/// larger one with a tighter false positive rate is added. The rates form a
+/// geometric series, so the overall false positive rate is bounded by the
+/// requested one however many sub-filters there are. (The bound assumes
+/// ideal hash functions; in practice it can be exceeded by a few percent.)
+#[derive(Clone)]
+pub struct ScalableBloomFilter<T: ?Sized> {
+    filters: Vec<BloomFilter<T>>,
+    /// Items inserted into the newest filter.
+    newest_len: u64,
+    /// Capacity of the newest filter.
+    newest_capacity: u64,
+    /// False positive rate of the newest filter.
+    newest_rate: f64,
+    fp_rate: f64,
+    hashes: [HashKind; 2],
+}
+
+impl<T: Hash + ?Sized> ScalableBloomFilter<T> {
+    /// Creates a filter whose first sub-filter holds `initial_capacity` items
+    /// and whose overall false positive rate stays below `fp_rate`.
+    pub fn new(initial_capacity: u64, fp_rate: f64) -> Self {
+        ScalableBloomFilter::with_hashes(initial_capacity, fp_rate, DEFAULT_HASHES)
+    }
+
+    /// Like `new`, but every sub-filter uses the given pair of hash
+    /// functions.
+    pub fn with_hashes(initial_capacity: u64, fp_rate: f64, hashes: [HashKind; 2]) -> Self {
+        assert!(fp_rate > 0.0 && fp_rate < 1.0, "false positive rate must be in (0, 1)");
+        let first_rate = fp_rate * (1.0 - TIGHTENING);
+        let capacity = initial_capacity.max(1);
+        ScalableBloomFilter {
+            filters: vec![sub_filter(capacity, first_rate, hashes)],
+            newest_len: 0,
+            newest_capacity: capacity,
+            newest_rate: first_rate,
+            fp_rate,
+            hashes,
+        }
+    }
+
+    /// Adds `item` to the set. Returns `false` if it was (probably) already
+    /// there, in which case nothing is stored.
+    pub fn insert(&mut self, item: &T) -> bool {
+        if self.contains(item) {
+            return false;
+        }
+        if self.newest_len >= self.newest_capacity {
+            self.newest_capacity = self.newest_capacity.saturating_mul(GROWTH);
+            self.newest_rate *= TIGHTENING;
+            self.newest_len = 0;
+            self.filters.push(sub_filter(self.newest_capacity, self.newest_rate,
+                                         self.hashes));
+        }
+        self.newest_len += 1;
+        self.filters.last_mut().unwrap().insert(item)
+    }
+
+    /// Returns `false` if `item` is definitely not in the set, and `true`
+    /// if it probably is.
+    pub fn contains(&self, item: &T) -> bool {
+        self.filters.iter().any(|f| f.contains(item))
+    }
+}
+
+impl<T: ?Sized> ScalableBloomFilter<T> {
+    /// Number of sub-filters allocated so far.
+    pub fn num_filters(&self) -> usize { self.filters.len() }
+
+    /// The false positive rate the filter was asked to stay below.
+    pub fn target_rate(&self) -> f64 { self.fp_rate }
+
+    /// Upper bound on the current false positive rate: the chance that at
+    /// least one sub-filter, filled to capacity, reports a false positive.
+    pub fn false_positive_bound(&self) -> f64 {
+        let first = self.fp_rate * (1.0 - TIGHTENING);
+        let none = (0..self.filters.len() as i32)
+            .map(|i| 1.0 - first * TIGHTENING.powi(i))
+            .product::<f64>();
+        1.0 - none
+    }
+
+    /// Total number of bits across all sub-filters.
+    pub fn num_bits(&self) -> u64 {
+        self.filters.iter().map(|f| f.num_bits()).sum()
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for ScalableBloomFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ScalableBloomFilter")
+            .field("filters", &self.filters)
+            .field("fp_rate", &self.fp_rate)
+            .finish()
+    }
+}
+
+fn sub_filter<T: Hash + ?Sized>(capacity: u64, fp_rate: f64, hashes: [HashKind; 2])
+        -> BloomFilter<T> {
+    let num_bits = optimal_num_bits(capacity, fp_rate);
+    BloomFilter::with_hashes(num_bits, optimal_num_hashes(num_bits, capacity), hashes)
+}
+
+/// Number of bits for `items` items at a false positive rate of `fp_rate`:
+/// `-n ln(p) / ln(2)^2`.
+pub fn optimal_num_bits(items: u64, fp_rate: f64) -> u64 {
+    assert!(fp_rate > 0.0 && fp_rate < 1.0, "false positive rate must be in (0, 1)");
+    let bits = -(items.max(1) as f64) * fp_rate.ln() / (LN_2 * LN_2);
+    bits.ceil() as u64
+}
+
+/// Number of hashes that minimises the false positive rate of a filter of
+/// `num_bits` bits holding `items` items: `m / n * ln(2)`.
+pub fn optimal_num_hashes(num_bits: u64, items: u64) -> u32 {
+    let k = num_bits as f64 / items.max(1) as f64 * LN_2;
+    (k.round() as u32).max(1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hash;
+    use std::collections::HashMap;
+    use std::hash::BuildHasherDefault;
+
+    #[test]
+    fn test_hashers_match_functions() {
+        for s in &["", "a", "carrot", "a somewhat longer string of bytes"] {
+            let bytes = s.as_bytes();
+            assert_eq!(HashKind::Djb2.hash_bytes(bytes), hash::djb2(bytes));
+            assert_eq!(HashKind::Fnv.hash_bytes(bytes), fnv(bytes));
+            assert_eq!(HashKind::Jenkins.hash_bytes(bytes), jenkins(bytes));
+            assert_eq!(HashKind::Murmur3.hash_bytes(bytes), hash::murmur3_32(bytes));
+            assert_eq!(HashKind::XxHash64.hash_bytes(bytes), hash::xxh64(bytes));
+            assert_eq!(HashKind::SipHash.hash_bytes(bytes), hash::siphash24(bytes));
+
+            // Writing in pieces gives the same result as writing at once.
+            let mut h = JenkinsHasher::default();
+            for chunk in bytes.chunks(3) {
+                h.write(chunk);
+            }
+            assert_eq!(h.finish(), jenkins(bytes));
+        }
+    }
+
+    #[test]
+    fn test_hasher_in_hash_map() {
+        let mut map: HashMap<&str, i32, BuildHasherDefault<Fnv1aHasher64>> = HashMap::default();
+        map.insert("apple", 1);
+        map.insert("fig", 2);
+        assert_eq!(map.get("fig"), Some(&2));
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let mut filter = BloomFilter::new(1000, 4);
+        for i in 0..200u32 {
+            filter.insert(&i);
+        }
+        for i in 0..200u32 {
+            assert!(filter.contains(&i));
+        }
+    }
+
+    #[test]
+    fn test_unsized_items() {
+        let mut filter: BloomFilter<str> = BloomFilter::with_rate(10, 0.01);
+        assert!(filter.is_empty());
+        assert!(filter.insert("carrot"));
+        assert!(!filter.insert("carrot"));
+        assert!(filter.contains("carrot"));
+        assert!(!filter.contains("bread"));
+    }
+
+    #[test]
+    fn test_sizing() {
+        assert_eq!(optimal_num_bits(1000, 0.01), 9586);
+        assert_eq!(optimal_num_hashes(9586, 1000), 7);
+
+        let mut filter = BloomFilter::with_rate(10_000, 0.01);
+        for i in 0..10_000u64 {
+            filter.insert(&i);
+        }
+        let false_positives = (10_000..110_000u64).filter(|i| filter.contains(i)).count();
+        let rate = false_positives as f64 / 100_000.0;
+        assert!(rate < 0.015, "false positive rate {}", rate);
+        assert!((filter.false_positive_rate() - 0.01).abs() < 0.003);
+    }
+
+    #[test]
+    fn test_hash_kinds() {
+        let kinds = [HashKind::Djb2, HashKind::Fnv, HashKind::Jenkins, HashKind::Murmur3,
+                     HashKind::XxHash64, HashKind::SipHash];
+        for &kind in &kinds {
+            assert_eq!(HashKind::from_id(kind.id()), Some(kind));
+        }
+
+        // The better-mixing pairs hit the target rate on sequential keys.
+        for &hashes in &[[HashKind::XxHash64, HashKind::Murmur3],
+                         [HashKind::SipHash, HashKind::XxHash64]] {
+            let num_bits = optimal_num_bits(10_000, 0.01);
+            let mut filter = BloomFilter::with_hashes(
+                num_bits, optimal_num_hashes(num_bits, 10_000), hashes);
+            for i in 0..10_000u64 {
+                filter.insert(&i);
+            }
+            let false_positives = (10_000..110_000u64).filter(|i| filter.contains(i)).count();
+            let rate = false_positives as f64 / 100_000.0;
+            assert!(rate < 0.012, "{:?}: false positive rate {}", hashes, rate);
+
+            let read = BloomFilter::<u64>::read_from(&serialize(&filter)[..]).unwrap();
+            assert_eq!(read, filter);
+        }
+    }
+
+    #[test]
+    fn test_estimated_len() {
+        let mut filter = BloomFilter::with_rate(5_000, 0.01);
+        assert_eq!(filter.estimated_len(), 0.0);
+        for i in 0..5_000u64 {
+            filter.insert(&i);
+        }
+        let estimate = filter.estimated_len();
+        assert!((estimate - 5_000.0).abs() < 100.0, "estimate {}", estimate);
+    }
+
+    #[test]
+    fn test_union_and_intersect() {
+        let mut a = BloomFilter::new(4096, 3);
+        let mut b = BloomFilter::new(4096, 3);
+        for i in 0..100u32 {
+            a.insert(&i);
+            b.insert(&(i + 50));
+        }
+
+        let mut both = a.clone();
+        both.union(&b).unwrap();
+        assert!((0..150u32).all(|i| both.contains(&i)));
+
+        let mut common = a.clone();
+        common.intersect(&b).unwrap();
+        assert!((50..100u32).all(|i| common.contains(&i)));
+        assert!(common.count_ones() < a.count_ones());
+
+        let other_shape = BloomFilter::with_hashes(4096, 3, [HashKind::Djb2, HashKind::Fnv]);
+        assert_eq!(a.union(&other_shape), Err(IncompatibleFilters));
+        assert_eq!(a.intersect(&BloomFilter::new(4096, 4)), Err(IncompatibleFilters));
+    }
+
+    fn sample_filter() -> BloomFilter<str> {
+        let mut filter = BloomFilter::with_hashes(1000, 5, [HashKind::Jenkins, HashKind::Djb2]);
+        for word in &["apple", "blueberry", "carrot", "date"] {
+            filter.insert(*word);
+        }
+        filter
+    }
+
+    fn serialize<T: Hash + ?Sized>(filter: &BloomFilter<T>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        filter.write_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let filter = sample_filter();
+        let bytes = serialize(&filter);
+        assert_eq!(bytes.len(), 20 + 16 * 8 + 8);
+
+        let read: BloomFilter<str> = BloomFilter::read_from(&bytes[..]).unwrap();
+        assert_eq!(read, filter);
+        assert!(read.contains("carrot"));
+        assert!(!read.contains("bread"));
+
+        for &num_bits in &[1, 63, 64, 65, 4096] {
+            let mut filter = BloomFilter::new(num_bits, 3);
+            for i in 0..num_bits / 8 {
+                filter.insert(&i);
+            }
+            let read = BloomFilter::<u64>::read_from(&serialize(&filter)[..]).unwrap();
+            assert_eq!(read, filter);
+        }
+    }
+
+    #[test]
+    fn test_serialized_layout() {
+        let mut filter: BloomFilter<str> = BloomFilter::new(70, 1);
+        filter.bits = vec![0x0102030405060708, 0x3f];
+        let bytes = serialize(&filter);
+        assert_eq!(&bytes[..20], &[b'B', b'L', b'M', b'F', 1, 0, 2, 3, 1, 0, 0, 0,
+                                   70, 0, 0, 0, 0, 0, 0, 0][..]);
+        assert_eq!(&bytes[20..36], &[8, 7, 6, 5, 4, 3, 2, 1, 0x3f, 0, 0, 0, 0, 0, 0, 0][..]);
+        assert_eq!(&bytes[36..], &fnv(&bytes[..36]).to_le_bytes()[..]);
+    }
+
+    #[test]
+    fn test_deserialize_truncated() {
+        let bytes = serialize(&sample_filter());
+        for len in 0..bytes.len() {
+            match BloomFilter::<str>::read_from(&bytes[..len]) {
+                Err(DecodeError::Truncated) => {}
+                other => panic!("length {}: {:?}", len, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_deserialize_corrupted() {
+        let bytes = serialize(&sample_filter());
+        for i in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[i] ^= 0x10;
+            match BloomFilter::<str>::read_from(&corrupted[..]) {
+                Err(DecodeError::BadMagic) => assert!(i < 4),
+                Err(DecodeError::UnsupportedVersion(_)) => assert!((4..6).contains(&i)),
+                // A corrupted size reads past the end of the input.
+                Err(DecodeError::Truncated) => assert!((12..20).contains(&i)),
+                Err(DecodeError::ChecksumMismatch { .. }) => {}
+                other => panic!("byte {}: {:?}", i, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_deserialize_invalid_contents() {
+        // Valid checksums over invalid contents.
+        let with_checksum = |mut bytes: Vec<u8>| {
+            let len = bytes.len();
+            let checksum = fnv(&bytes[..len - 8]);
+            bytes[len - 8..].copy_from_slice(&checksum.to_le_bytes());
+            bytes
+        };
+
+        let mut bytes = serialize(&sample_filter());
+        bytes[6] = 9;
+        match BloomFilter::<str>::read_from(&with_checksum(bytes)[..]) {
+            Err(DecodeError::UnknownHash(9)) => {}
+            other => panic!("{:?}", other),
+        }
+
+        let mut bytes = serialize(&sample_filter());
+        bytes[8..12].copy_from_slice(&[0; 4]);
+        match BloomFilter::<str>::read_from(&with_checksum(bytes)[..]) {
+            Err(DecodeError::InvalidHeader) => {}
+            other => panic!("{:?}", other),
+        }
+
+        // 1000 bits leave the top 24 bits of the last word unused.
+        let mut bytes = serialize(&sample_filter());
+        let last_byte = bytes.len() - 9;
+        bytes[last_byte] = 0x80;
+        match BloomFilter::<str>::read_from(&with_checksum(bytes)[..]) {
+            Err(DecodeError::StrayBits) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_counting_insert_remove() {
+        let mut filter = CountingBloomFilter::with_rate(100, 0.01);
+        for i in 0..100u32 {
+            filter.insert(&i);
+        }
+        assert!((0..100u32).all(|i| filter.contains(&i)));
+
+        for i in 0..50u32 {
+            assert!(filter.remove(&i));
+        }
+        // The rest are still there, the removed ones are (almost surely)
+        // gone.
+        assert!((50..100u32).all(|i| filter.contains(&i)));
+        assert!((0..50u32).filter(|i| filter.contains(i)).count() < 5);
+
+        for i in 50..100u32 {
+            assert!(filter.remove(&i));
+        }
+        assert!(filter.is_empty());
+        assert!(!filter.remove(&7));
+    }
+
+    #[test]
+    fn test_counting_multiset() {
+        let mut filter: CountingBloomFilter<str> = CountingBloomFilter::new(256, 3);
+        filter.insert("apple");
+        filter.insert("apple");
+        filter.remove("apple");
+        assert!(filter.contains("apple"));
+        filter.remove("apple");
+        assert!(!filter.contains("apple"));
+    }
+
+    #[test]
+    fn test_counting_remove_false_positive() {
+        // With 3 counters and 4 hashes, an item often hits a slot twice.
+        let mut filter = CountingBloomFilter::new(3, 4);
+        filter.insert(&0u32);
+        let counters = |filter: &CountingBloomFilter<u32>| {
+            (0..3).map(|i| filter.counter(i)).collect::<Vec<_>>()
+        };
+        let before = counters(&filter);
+
+        let repeats_a_one = |item: &u32| {
+            let slots: Vec<u64> = bit_indices(item, DEFAULT_HASHES, 3, 4).collect();
+            let hits = |i: u64| slots.iter().filter(|&&j| j == i).count();
+            slots.iter().any(|&i| before[i as usize] == 1 && hits(i) > 1)
+        };
+        let false_positive = (1..1000u32)
+            .find(|i| filter.contains(i) && repeats_a_one(i))
+            .unwrap();
+        assert!(filter.remove(&false_positive));
+        // Every counter went down or stayed put; none wrapped around.
+        let after = counters(&filter);
+        assert!(after.iter().zip(&before).all(|(a, b)| a <= b), "{:?} -> {:?}", before, after);
+    }
+
+    #[test]
+    fn test_counting_saturation_is_sticky() {
+        let mut filter = CountingBloomFilter::new(64, 2);
+        for _ in 0..20 {
+            filter.insert(&"hot");
+        }
+        for _ in 0..20 {
+            filter.remove(&"hot");
+        }
+        // Saturated counters never go back down, so no false negative can
+        // appear even after over-removal.
+        assert!(filter.contains(&"hot"));
+    }
+
+    #[test]
+    fn test_scalable_grows() {
+        let mut filter = ScalableBloomFilter::new(1000, 0.01);
+        assert_eq!(filter.num_filters(), 1);
+        for i in 0..50_000u64 {
+            filter.insert(&i);
+        }
+        assert!(filter.num_filters() > 4);
+        assert!((0..50_000u64).all(|i| filter.contains(&i)));
+        assert!(!filter.insert(&1234));
+
+        assert!(filter.false_positive_bound() < filter.target_rate());
+        let false_positives = (50_000..250_000u64).filter(|i| filter.contains(i)).count();
+        let rate = false_positives as f64 / 200_000.0;
+        assert!(rate < 0.0125, "false positive rate {}", rate);
+    }
+
+    #[test]
+    fn test_scalable_with_hashes() {
+        let mut filter = ScalableBloomFilter::with_hashes(
+            4, 0.05, [HashKind::Djb2, HashKind::Jenkins]);
+        for word in &["apple", "blueberry", "carrot", "date", "eggplant", "fig"] {
+            filter.insert(*word);
+        }
+        assert_eq!(filter.num_filters(), 2);
+        assert!(filter.contains("fig"));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut filter = BloomFilter::new(64, 2);
+        filter.insert(&1u8);
+        assert!(!filter.is_empty());
+        filter.clear();
+        assert!(filter.is_empty());
+        assert!(!filter.contains(&1u8));
+    }
+}
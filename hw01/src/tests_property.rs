@@ -0,0 +1,487 @@
+#![cfg(test)]
+
+//! Property tests: each property is checked against a few hundred random
+//! inputs, and a failing input is shrunk to a small counterexample before
+//! it is reported.
+//!
+//! Every failure report includes the seed of the failing case. Setting
+//! `HW01_PROP_SEED` to that seed makes it the first case that runs, and
+//! `HW01_PROP_CASES` changes the number of cases per property.
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::env;
+use std::fmt::Debug;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use problem1::{checked_sum, dedup, dedup_in_place, dedup_sorted, filter, saturating_sum, sum,
+               widening_sum, wrapping_sum};
+use problem2::sieve;
+use problem3::{hanoi, hanoi_pegs, HanoiState};
+use problem4::{BloomFilter, CountingBloomFilter, ScalableBloomFilter};
+
+const DEFAULT_CASES: usize = 256;
+
+/// Upper bound on the number of shrink steps taken for one failure.
+const MAX_SHRINKS: usize = 1_000;
+
+/// SplitMix64: tiny, fast, and good enough to drive test generation.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self { Rng(seed) }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[lo, hi]`.
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        (lo as i128 + (self.next_u64() as u128 % span) as i128) as i64
+    }
+}
+
+/// A source of random test inputs that knows how to make them smaller.
+pub trait Gen {
+    type Value: Clone + Debug;
+
+    /// A random value. `size` is a random percentage that scales how big
+    /// the value is, so small inputs stay common alongside large ones.
+    fn generate(&self, rng: &mut Rng, size: usize) -> Self::Value;
+
+    /// Values "smaller" than `value`, most aggressive first. Must not
+    /// include `value` itself, or shrinking could loop.
+    fn shrink(&self, value: &Self::Value) -> Vec<Self::Value>;
+}
+
+/// Integers in `[lo, hi]`, shrinking towards zero (or the bound nearest to
+/// it).
+pub struct Ints<T> {
+    lo: T,
+    hi: T,
+}
+
+pub fn ints<T>(lo: T, hi: T) -> Ints<T> {
+    Ints { lo, hi }
+}
+
+impl<T> Gen for Ints<T>
+    where T: Copy + Debug + Into<i64> + TryFrom<i64>
+{
+    type Value = T;
+
+    fn generate(&self, rng: &mut Rng, _size: usize) -> T {
+        from_i64(rng.range(self.lo.into(), self.hi.into()))
+    }
+
+    fn shrink(&self, &value: &T) -> Vec<T> {
+        let (lo, hi, v) = (self.lo.into(), self.hi.into(), value.into());
+        let target = 0.max(lo).min(hi);
+        // target, then halfway back to v, a quarter of the way, ..., v -+ 1.
+        let mut shrunk = Vec::new();
+        let mut distance = v - target;
+        while distance != 0 {
+            shrunk.push(from_i64(v - distance));
+            distance /= 2;
+        }
+        shrunk
+    }
+}
+
+fn from_i64<T: TryFrom<i64>>(v: i64) -> T {
+    match T::try_from(v) {
+        Ok(v) => v,
+        Err(_) => panic!("{} out of range", v),
+    }
+}
+
+/// Vectors of up to `max_len` elements (scaled down for small sizes),
+/// shrinking by dropping elements and then by shrinking them.
+pub struct Vecs<G> {
+    elem: G,
+    max_len: usize,
+}
+
+pub fn vecs<G: Gen>(elem: G, max_len: usize) -> Vecs<G> {
+    Vecs { elem, max_len }
+}
+
+impl<G: Gen> Gen for Vecs<G> {
+    type Value = Vec<G::Value>;
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> Self::Value {
+        let max_len = self.max_len * (size + 1) / 101;
+        let len = rng.range(0, max_len as i64) as usize;
+        (0..len).map(|_| self.elem.generate(rng, size)).collect()
+    }
+
+    fn shrink(&self, value: &Self::Value) -> Vec<Self::Value> {
+        let mut shrunk = Vec::new();
+        // Remove chunks, from half the vector down to single elements.
+        let mut chunk = value.len() / 2;
+        while chunk > 0 {
+            let mut start = 0;
+            while start + chunk <= value.len() {
+                let mut smaller = value[..start].to_vec();
+                smaller.extend_from_slice(&value[start + chunk..]);
+                shrunk.push(smaller);
+                start += chunk;
+            }
+            chunk /= 2;
+        }
+        if value.len() == 1 {
+            shrunk.push(Vec::new());
+        }
+        // Then shrink elements in place.
+        for (i, elem) in value.iter().enumerate() {
+            for smaller_elem in self.elem.shrink(elem) {
+                let mut smaller = value.clone();
+                smaller[i] = smaller_elem;
+                shrunk.push(smaller);
+            }
+        }
+        shrunk
+    }
+}
+
+impl<A: Gen, B: Gen> Gen for (A, B) {
+    type Value = (A::Value, B::Value);
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> Self::Value {
+        (self.0.generate(rng, size), self.1.generate(rng, size))
+    }
+
+    fn shrink(&self, value: &Self::Value) -> Vec<Self::Value> {
+        let firsts = self.0.shrink(&value.0).into_iter().map(|a| (a, value.1.clone()));
+        let seconds = self.1.shrink(&value.1).into_iter().map(|b| (value.0.clone(), b));
+        firsts.chain(seconds).collect()
+    }
+}
+
+/// A property failure, after shrinking.
+#[derive(Debug)]
+pub struct Failure<V> {
+    /// Seed of the case that failed.
+    pub seed: u64,
+    pub original: V,
+    pub shrunk: V,
+    pub message: String,
+}
+
+/// Runs `prop` against `cases` inputs drawn from `gen`, with the first case
+/// using `seed` and the rest seeded from it. Returns the first failure,
+/// shrunk as far as possible.
+pub fn find_failure<G, P>(gen: &G, prop: P, seed: u64, cases: usize)
+                          -> Option<Failure<G::Value>>
+    where G: Gen, P: Fn(&G::Value) -> Result<(), String>
+{
+    let mut seeds = Rng::new(seed);
+    for case in 0..cases {
+        let case_seed = if case == 0 { seed } else { seeds.next_u64() };
+        // The size comes from the case's own seed, so that the seed alone is
+        // enough to regenerate the input.
+        let mut rng = Rng::new(case_seed);
+        let size = rng.range(0, 100) as usize;
+        let value = gen.generate(&mut rng, size);
+        let message = match prop(&value) {
+            Ok(()) => continue,
+            Err(message) => message,
+        };
+
+        // Greedy shrinking: keep taking the first smaller value that still
+        // fails until none does.
+        let (mut shrunk, mut message) = (value.clone(), message);
+        let mut steps = 0;
+        'shrink: while steps < MAX_SHRINKS {
+            for candidate in gen.shrink(&shrunk) {
+                steps += 1;
+                if let Err(m) = prop(&candidate) {
+                    shrunk = candidate;
+                    message = m;
+                    continue 'shrink;
+                }
+            }
+            break;
+        }
+        return Some(Failure { seed: case_seed, original: value, shrunk, message });
+    }
+    None
+}
+
+/// Checks `prop` like `find_failure`, panicking with a reproducible report
+/// on failure. The seed and number of cases come from the environment when
+/// set.
+pub fn check<G, P>(name: &str, gen: G, prop: P)
+    where G: Gen, P: Fn(&G::Value) -> Result<(), String>
+{
+    let seed = match env::var("HW01_PROP_SEED") {
+        Ok(s) => parse_seed(&s),
+        Err(_) => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            now.as_secs() ^ ((now.subsec_nanos() as u64) << 32)
+        }
+    };
+    let cases = match env::var("HW01_PROP_CASES") {
+        Ok(s) => s.parse().expect("HW01_PROP_CASES must be a number"),
+        Err(_) => DEFAULT_CASES,
+    };
+
+    if let Some(f) = find_failure(&gen, prop, seed, cases) {
+        panic!("property `{}` failed: {}\n  minimal input: {:?}\n  original input: {:?}\n  \
+                reproduce with HW01_PROP_SEED={:#x}",
+               name, f.message, f.shrunk, f.original, f.seed);
+    }
+}
+
+fn parse_seed(s: &str) -> u64 {
+    let parsed = match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.expect("HW01_PROP_SEED must be a decimal or 0x-prefixed hex number")
+}
+
+/// Like `assert_eq!`, but returns a failure message instead of panicking.
+macro_rules! prop_assert_eq {
+    ($left:expr, $right:expr) => {{
+        let (left, right) = (&$left, &$right);
+        if left != right {
+            return Err(format!("{} != {}: {:?} != {:?}",
+                               stringify!($left), stringify!($right), left, right));
+        }
+    }};
+}
+
+macro_rules! prop_assert {
+    ($cond:expr, $($msg:tt)+) => {
+        if !$cond {
+            return Err(format!($($msg)+));
+        }
+    };
+}
+
+//
+// The harness itself
+//
+
+#[test]
+fn test_shrinks_to_minimal_counterexample() {
+    let gen = vecs(ints(0i32, 1_000), 50);
+    let prop = |v: &Vec<i32>| -> Result<(), String> {
+        prop_assert!(v.iter().all(|&x| x < 500), "found a large element");
+        Ok(())
+    };
+    let failure = find_failure(&gen, prop, 1, 1_000).expect("property should fail");
+    assert_eq!(failure.shrunk, vec![500]);
+}
+
+#[test]
+fn test_failures_reproduce_from_seed() {
+    let gen = vecs(ints(-100i32, 100), 20);
+    let prop = |v: &Vec<i32>| -> Result<(), String> {
+        prop_assert!(v.len() < 8, "too long");
+        Ok(())
+    };
+    let failure = find_failure(&gen, prop, 42, 1_000).expect("property should fail");
+    let again = find_failure(&gen, prop, failure.seed, 1).expect("seed should reproduce");
+    assert_eq!(again.original, failure.original);
+    assert_eq!(again.shrunk.len(), 8);
+}
+
+#[test]
+fn test_int_shrinking_stays_in_range() {
+    let gen = ints(10u32, 20);
+    assert_eq!(gen.shrink(&17), vec![10, 14, 16]);
+    assert_eq!(gen.shrink(&10), vec![]);
+    assert_eq!(ints(-5i32, 5).shrink(&-4), vec![0, -2, -3]);
+}
+
+//
+// Problem 1
+//
+
+#[test]
+fn prop_sum_matches_fold() {
+    check("sum matches fold", vecs(ints(-100_000i32, 100_000), 200), |v| {
+        // Folding in i64 can't overflow for these inputs.
+        let folded = v.iter().fold(0i64, |acc, &x| acc + x as i64);
+        prop_assert_eq!(sum(v) as i64, folded);
+        prop_assert_eq!(checked_sum(v).map(i64::from), Some(folded));
+        Ok(())
+    });
+}
+
+#[test]
+fn prop_sum_is_order_independent() {
+    check("sum is order independent", vecs(ints(i32::MIN, i32::MAX), 100), |v| {
+        let mut reversed = v.clone();
+        reversed.reverse();
+        prop_assert_eq!(saturating_sum(v), saturating_sum(&reversed));
+        prop_assert_eq!(wrapping_sum(v), wrapping_sum(&reversed));
+        let wide = v.iter().map(|&x| x as i64).sum::<i64>();
+        prop_assert_eq!(widening_sum(v), wide);
+        Ok(())
+    });
+}
+
+/// The obviously correct, quadratic dedup.
+fn dedup_reference(vs: &[i32]) -> Vec<i32> {
+    let mut result = Vec::new();
+    for &v in vs {
+        if !result.contains(&v) {
+            result.push(v);
+        }
+    }
+    result
+}
+
+#[test]
+fn prop_dedup_matches_reference() {
+    // A small range of values, so inputs are full of duplicates.
+    check("dedup matches reference", vecs(ints(-10i32, 10), 100), |v| {
+        let expected = dedup_reference(v);
+        prop_assert_eq!(dedup(v), expected);
+
+        let mut in_place = v.clone();
+        dedup_in_place(&mut in_place);
+        prop_assert_eq!(in_place, expected);
+
+        let mut sorted = v.clone();
+        sorted.sort();
+        let mut sorted_expected = expected.clone();
+        sorted_expected.sort();
+        prop_assert_eq!(dedup_sorted(&sorted), sorted_expected);
+        Ok(())
+    });
+}
+
+#[test]
+fn prop_filter_keeps_matching_elements_in_order() {
+    check("filter keeps matching elements", vecs(ints(-1_000i32, 1_000), 100), |v| {
+        let even = filter(v, &|x| x % 2 == 0);
+        let expected: Vec<i32> = v.iter().cloned().filter(|x| x % 2 == 0).collect();
+        prop_assert_eq!(even, expected);
+        Ok(())
+    });
+}
+
+//
+// Problem 2
+//
+
+fn trial_division(n: u32) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d))
+}
+
+#[test]
+fn prop_sieve_matches_trial_division() {
+    check("sieve matches trial division", ints(0u32, 5_000), |&n| {
+        let expected: Vec<u32> = (0..n).filter(|&i| trial_division(i)).collect();
+        prop_assert_eq!(sieve(n), expected);
+        Ok(())
+    });
+}
+
+//
+// Problem 3
+//
+
+#[test]
+fn prop_hanoi_solves_puzzle() {
+    check("hanoi solves the puzzle", ints(0u32, 16), |&n| {
+        let moves = hanoi(n);
+        prop_assert_eq!(moves.len() as u64, (1u64 << n) - 1);
+        let mut state = HanoiState::new(n, 3);
+        prop_assert_eq!(state.replay(moves), Ok(()));
+        prop_assert!(state.is_solved(), "{} discs not all on the last peg", n);
+        Ok(())
+    });
+}
+
+#[test]
+fn prop_hanoi_pegs_solves_puzzle() {
+    check("hanoi_pegs solves the puzzle", (ints(0u32, 14), ints(3u8, 7)), |&(n, pegs)| {
+        let moves = hanoi_pegs(n, pegs);
+        // Extra pegs never hurt.
+        prop_assert!(moves.len() <= hanoi(n).len(), "{} moves is worse than 3 pegs",
+                     moves.len());
+        let mut state = HanoiState::new(n, pegs);
+        prop_assert_eq!(state.replay(moves), Ok(()));
+        prop_assert!(state.is_solved(), "{} discs not all on the last peg", n);
+        Ok(())
+    });
+}
+
+//
+// Problem 4
+//
+
+fn words() -> Vecs<Vecs<Ints<u8>>> {
+    vecs(vecs(ints(0u8, 255), 12), 300)
+}
+
+#[test]
+fn prop_bloom_has_no_false_negatives() {
+    check("bloom filter has no false negatives", words(), |items| {
+        let mut filter: BloomFilter<[u8]> = BloomFilter::with_rate(items.len() as u64, 0.05);
+        for item in items {
+            filter.insert(item);
+        }
+        for item in items {
+            prop_assert!(filter.contains(item), "lost {:?}", item);
+        }
+
+        // Nor does a round trip through the binary format lose anything.
+        let mut bytes = Vec::new();
+        filter.write_to(&mut bytes).unwrap();
+        let decoded: BloomFilter<[u8]> = BloomFilter::read_from(&bytes[..]).unwrap();
+        for item in items {
+            prop_assert!(decoded.contains(item), "lost {:?} after decoding", item);
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn prop_counting_bloom_keeps_unremoved_items() {
+    check("counting bloom filter keeps unremoved items", (words(), words()), |pair| {
+        let (ref kept, ref removed) = *pair;
+        let mut filter: CountingBloomFilter<[u8]> =
+            CountingBloomFilter::with_rate((kept.len() + removed.len()) as u64, 0.05);
+        for item in kept.iter().chain(removed) {
+            filter.insert(item);
+        }
+        for item in removed {
+            filter.remove(item);
+        }
+        for item in kept {
+            prop_assert!(filter.contains(item), "lost {:?}", item);
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn prop_scalable_bloom_has_no_false_negatives() {
+    check("scalable bloom filter has no false negatives", words(), |items| {
+        // A tiny initial capacity forces several sub-filters.
+        let mut filter: ScalableBloomFilter<[u8]> = ScalableBloomFilter::new(4, 0.05);
+        let mut seen = HashSet::new();
+        for item in items {
+            let added = filter.insert(item);
+            // A fresh item can look like a false positive, but an item seen
+            // before must never be added again.
+            prop_assert!(!(added && seen.contains(item)), "re-added {:?}", item);
+            seen.insert(item);
+        }
+        for item in items {
+            prop_assert!(filter.contains(item), "lost {:?}", item);
+        }
+        Ok(())
+    });
+}
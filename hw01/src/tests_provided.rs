@@ -1,9 +1,10 @@
 #![cfg(test)]
+#![allow(clippy::bool_assert_comparison)]
 
 use problem1::{sum, dedup, filter};
-// use problem2::sieve;
-// use problem3::hanoi;
-// use problem4::{bloom, djb2, fnv, jenkins};
+use problem2::sieve;
+use problem3::hanoi;
+use problem4::{bloom, djb2, fnv, jenkins};
 
 //
 // Problem 1
@@ -37,36 +38,36 @@ fn test_filter_small() {
     assert_eq!(filter(&vs, &even_predicate), vec![2, 4]);
 }
 
-// //
-// // Problem 2
-// //
+//
+// Problem 2
+//
 
-// #[test]
-// fn test_sieve_basic() {
-//     assert_eq!(vec![2, 3, 5, 7, 11], sieve(12));
-// }
+#[test]
+fn test_sieve_basic() {
+    assert_eq!(vec![2, 3, 5, 7, 11], sieve(12));
+}
 
-// //
-// // Problem 3
-// //
+//
+// Problem 3
+//
 
-// #[test]
-// fn test_hanoi_1_disks() {
-//     let result = hanoi(1);
-//     assert_eq!(vec![(1, 3)], result);
-//     assert_eq!(1, result.len());
-// }
+#[test]
+fn test_hanoi_1_disks() {
+    let result = hanoi(1);
+    assert_eq!(vec![(1, 3)], result);
+    assert_eq!(1, result.len());
+}
 
-// //
-// // Problem 4
-// //
+//
+// Problem 4
+//
 
-// #[test]
-// fn test_bloom_foods() {
-//     let data = vec!["apple", "blueberry", "carrot", "date", "eggplant",
-//         "fig", "grapefruit"];
-//     let hashes = [djb2, fnv, jenkins];
-//     assert_eq!(true, bloom(&data, hashes, "carrot"));
-//     assert_eq!(true, bloom(&data, hashes, "milk"));
-//     assert_eq!(false, bloom(&data, hashes, "bread"));
-// }
+#[test]
+fn test_bloom_foods() {
+    let data = vec!["apple", "blueberry", "carrot", "date", "eggplant",
+        "fig", "grapefruit"];
+    let hashes = [djb2, fnv, jenkins];
+    assert_eq!(true, bloom(&data, hashes, "carrot"));
+    assert_eq!(true, bloom(&data, hashes, "milk"));
+    assert_eq!(false, bloom(&data, hashes, "bread"));
+}
@@ -0,0 +1,88 @@
+//! Compares the heap footprint of the boxed `first::List` and the
+//! `unrolled::List`, counting bytes and allocations through a wrapping
+//! allocator. Run with `cargo bench --bench memory`.
+
+extern crate lists;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use lists::{first, unrolled};
+
+struct Counting;
+
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        ALLOCS.fetch_sub(1, Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Live heap bytes and allocations held by whatever `build` returns.
+fn footprint<L, F: FnOnce() -> L>(build: F) -> (usize, usize) {
+    let (bytes, allocs) = (BYTES.load(Ordering::Relaxed), ALLOCS.load(Ordering::Relaxed));
+    let list = build();
+    let used = (BYTES.load(Ordering::Relaxed) - bytes, ALLOCS.load(Ordering::Relaxed) - allocs);
+    drop(list);
+    used
+}
+
+fn report<T>(name: &str, len: usize, (bytes, allocs): (usize, usize)) {
+    let payload = len * mem::size_of::<T>();
+    println!("{:>10} {:>10} {:>12} {:>10} {:>12.2} {:>10.1}%",
+             name, len, bytes, allocs, bytes as f64 / len as f64,
+             100.0 * (bytes - payload) as f64 / bytes as f64);
+}
+
+fn measure<T: Default>(elem: &str) {
+    println!("element: {} ({} bytes)", elem, mem::size_of::<T>());
+    println!("{:>10} {:>10} {:>12} {:>10} {:>12} {:>11}",
+             "list", "len", "heap bytes", "allocs", "bytes/elem", "overhead");
+    for &len in &[10usize, 1_000, 1_000_000] {
+        report::<T>("boxed", len, footprint(|| {
+            let mut list = first::List::new();
+            for _ in 0..len {
+                list.push(T::default());
+            }
+            list
+        }));
+        report::<T>("unrolled", len, footprint(|| {
+            let mut list = unrolled::List::new();
+            for _ in 0..len {
+                list.push(T::default());
+            }
+            list
+        }));
+        // Worst case for the unrolled list: inserting behind the head
+        // splits it every time it fills, leaving nodes half full.
+        report::<T>("u. insert", len, footprint(|| {
+            let mut list = unrolled::List::new();
+            list.push(T::default());
+            for _ in 1..len {
+                list.insert(1, T::default());
+            }
+            list
+        }));
+    }
+    println!();
+}
+
+fn main() {
+    measure::<u8>("u8");
+    measure::<u64>("u64");
+    measure::<[u64; 4]>("[u64; 4]");
+}
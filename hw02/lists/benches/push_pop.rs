@@ -0,0 +1,56 @@
+//! Compares push/pop throughput of the boxed `first::List` and the
+//! index-linked `arena::List`. Run with `cargo bench --bench push_pop`.
+
+extern crate lists;
+
+mod common;
+
+use std::hint::black_box;
+
+use common::time;
+use lists::{arena, first};
+
+fn main() {
+    println!("{:>10} {:>10} {:>14} {:>14} {:>14}",
+             "list len", "lists", "boxed", "arena", "arena reused");
+
+    // Many short-lived lists, then a few long ones.
+    for &(len, lists) in &[(16usize, 10_000usize), (1_000, 100), (1_000_000, 1)] {
+        let boxed = time(|| {
+            for _ in 0..lists {
+                let mut list = first::List::new();
+                for i in 0..len {
+                    list.push(black_box(i));
+                }
+                while let Some(x) = list.pop() {
+                    black_box(x);
+                }
+            }
+        });
+        let arena = time(|| {
+            for _ in 0..lists {
+                let mut list = arena::List::new();
+                for i in 0..len {
+                    list.push(black_box(i));
+                }
+                while let Some(x) = list.pop() {
+                    black_box(x);
+                }
+            }
+        });
+        // One arena for every list: after the first, no allocation at all.
+        let mut shared = arena::List::with_capacity(len);
+        let reused = time(|| {
+            for _ in 0..lists {
+                for i in 0..len {
+                    shared.push(black_box(i));
+                }
+                while let Some(x) = shared.pop() {
+                    black_box(x);
+                }
+            }
+        });
+
+        println!("{:>10} {:>10} {:>14?} {:>14?} {:>14?}", len, lists, boxed, arena, reused);
+    }
+}
@@ -0,0 +1,113 @@
+//! Compares `skip_map::SkipMap` with the hw03 `BST` and std's `BTreeMap` on
+//! random keys. Run with `cargo bench --bench skip_map`.
+
+extern crate bst;
+extern crate lists;
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+
+use bst::second::BST;
+use common::time;
+use lists::skip_map::SkipMap;
+
+/// `n` distinct keys in a scrambled order.
+fn random_keys(n: u64) -> Vec<u64> {
+    // Multiplying by an odd constant permutes the integers mod 2^64.
+    (0..n).map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15)).collect()
+}
+
+fn main() {
+    println!("{:>8} {:>8} {:>14} {:>14} {:>14}", "keys", "op", "skip map", "bst", "btree map");
+
+    for &n in &[1_000u64, 100_000] {
+        let keys = random_keys(n);
+
+        let skip_insert = time(|| {
+            let mut map = SkipMap::with_seed(1);
+            for &k in &keys {
+                map.insert(k, k);
+            }
+            black_box(map);
+        });
+        let bst_insert = time(|| {
+            let mut set = BST::new();
+            for &k in &keys {
+                set.insert(k);
+            }
+            black_box(set);
+        });
+        let btree_insert = time(|| {
+            let mut map = BTreeMap::new();
+            for &k in &keys {
+                map.insert(k, k);
+            }
+            black_box(map);
+        });
+        println!("{:>8} {:>8} {:>14?} {:>14?} {:>14?}",
+                 n, "insert", skip_insert, bst_insert, btree_insert);
+
+        let skip: SkipMap<u64, u64> = keys.iter().map(|&k| (k, k)).collect();
+        let mut tree = BST::new();
+        for &k in &keys {
+            tree.insert(k);
+        }
+        let btree: BTreeMap<u64, u64> = keys.iter().map(|&k| (k, k)).collect();
+
+        // Look up every key, and as many that are missing.
+        let skip_get = time(|| {
+            for &k in &keys {
+                black_box(skip.get(&k));
+                black_box(skip.get(&(k + 1)));
+            }
+        });
+        let bst_get = time(|| {
+            for &k in &keys {
+                black_box(tree.search(&k));
+                black_box(tree.search(&(k + 1)));
+            }
+        });
+        let btree_get = time(|| {
+            for &k in &keys {
+                black_box(btree.get(&k));
+                black_box(btree.get(&(k + 1)));
+            }
+        });
+        println!("{:>8} {:>8} {:>14?} {:>14?} {:>14?}", n, "get", skip_get, bst_get, btree_get);
+
+        let skip_remove = time(|| {
+            let mut map: SkipMap<u64, u64> = keys.iter().map(|&k| (k, k)).collect();
+            for &k in &keys {
+                black_box(map.remove(&k));
+            }
+        });
+        let bst_remove = time(|| {
+            let mut set = BST::new();
+            for &k in &keys {
+                set.insert(k);
+            }
+            for &k in &keys {
+                black_box(set.remove(&k));
+            }
+        });
+        let btree_remove = time(|| {
+            let mut map: BTreeMap<u64, u64> = keys.iter().map(|&k| (k, k)).collect();
+            for &k in &keys {
+                black_box(map.remove(&k));
+            }
+        });
+        println!("{:>8} {:>8} {:>14?} {:>14?} {:>14?}",
+                 n, "build+rm", skip_remove, bst_remove, btree_remove);
+
+        // `BST` has no range queries, so only the maps race here.
+        let skip_range = time(|| {
+            black_box(skip.range(n..n << 40).count());
+        });
+        let btree_range = time(|| {
+            black_box(btree.range(n..n << 40).count());
+        });
+        println!("{:>8} {:>8} {:>14?} {:>14} {:>14?}", n, "range", skip_range, "-", btree_range);
+    }
+}
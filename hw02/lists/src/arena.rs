@@ -0,0 +1,355 @@
+//! A stack like `first::List`, but with its nodes stored in one `Vec`
+//! and linked by index, so `push` doesn't allocate once the arena has grown
+//! and nodes stay close together in memory.
+//!
+//! Popped slots go on a free list and are reused by later pushes. Memory is
+//! only returned to the allocator when the list itself is dropped (or
+//! `shrink_to_fit` finds the arena empty).
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::mem;
+
+/// Index of "no node".
+const NIL: usize = usize::MAX;
+
+pub struct List<T> {
+    slots: Vec<Slot<T>>,
+    head: usize,
+    /// First free slot, whose `Free` payload links to the next one.
+    free: usize,
+    len: usize,
+}
+
+enum Slot<T> {
+    Used { elem: T, next: usize },
+    Free { next_free: usize },
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { slots: Vec::new(), head: NIL, free: NIL, len: 0 }
+    }
+
+    /// An empty list with room for `capacity` nodes before the arena has to
+    /// grow.
+    pub fn with_capacity(capacity: usize) -> Self {
+        List { slots: Vec::with_capacity(capacity), head: NIL, free: NIL, len: 0 }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let slot = Slot::Used { elem, next: self.head };
+        self.head = if self.free != NIL {
+            let index = self.free;
+            self.free = match mem::replace(&mut self.slots[index], slot) {
+                Slot::Free { next_free } => next_free,
+                Slot::Used { .. } => unreachable!("free list points at a used slot"),
+            };
+            index
+        } else {
+            self.slots.push(slot);
+            self.slots.len() - 1
+        };
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.head == NIL {
+            return None;
+        }
+        let index = self.head;
+        let freed = Slot::Free { next_free: self.free };
+        match mem::replace(&mut self.slots[index], freed) {
+            Slot::Used { elem, next } => {
+                self.head = next;
+                self.free = index;
+                self.len -= 1;
+                Some(elem)
+            }
+            Slot::Free { .. } => unreachable!("list points at a free slot"),
+        }
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.get(self.head).map(|(elem, _)| elem)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        match self.slots.get_mut(self.head) {
+            Some(&mut Slot::Used { ref mut elem, .. }) => Some(elem),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of nodes the arena holds without growing.
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    /// Removes every element but keeps the arena's memory for reuse.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.head = NIL;
+        self.free = NIL;
+        self.len = 0;
+    }
+
+    /// Releases the arena's spare memory. Free slots in the middle of the
+    /// arena can't be released without renumbering nodes, so this only
+    /// helps once the list is empty or has never been popped.
+    pub fn shrink_to_fit(&mut self) {
+        if self.len == 0 {
+            self.clear();
+        }
+        self.slots.shrink_to_fit();
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { slots: &self.slots, next: self.head, len: self.len }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.slots.as_mut_ptr(),
+            num_slots: self.slots.len(),
+            next: self.head,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<(&T, usize)> {
+        match self.slots.get(index) {
+            Some(&Slot::Used { ref elem, next }) => Some((elem, next)),
+            _ => None,
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+/// Sizes the arena for every element up front, then pushes them in reverse
+/// so the first one yielded ends up at the head.
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        let mut list = List::with_capacity(elems.len());
+        for elem in elems.into_iter().rev() {
+            list.push(elem);
+        }
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct IntoIter<T>(List<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+pub struct Iter<'a, T: 'a> {
+    slots: &'a [Slot<T>],
+    next: usize,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self.slots.get(self.next) {
+            Some(&Slot::Used { ref elem, next }) => {
+                self.next = next;
+                self.len -= 1;
+                Some(elem)
+            }
+            _ => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Follows the links through a raw pointer to the slots, because the
+/// borrow checker can't see that the list never visits a slot twice.
+pub struct IterMut<'a, T: 'a> {
+    slots: *mut Slot<T>,
+    num_slots: usize,
+    next: usize,
+    len: usize,
+    _marker: PhantomData<&'a mut [Slot<T>]>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.next >= self.num_slots {
+            return None;
+        }
+        // SAFETY: `next` is in bounds, and the slots stay borrowed mutably
+        // for 'a. The links form an acyclic chain from the head, so each
+        // slot is visited at most once and the returned references never
+        // alias.
+        let slot = unsafe { &mut *self.slots.add(self.next) };
+        match *slot {
+            Slot::Used { ref mut elem, next } => {
+                self.next = next;
+                self.len -= 1;
+                Some(elem)
+            }
+            Slot::Free { .. } => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+
+    #[test]
+    fn basics() {
+        let mut list = List::new();
+        assert_eq!(list.pop(), None);
+
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), Some(2));
+
+        list.push(4);
+        list.push(5);
+        assert_eq!(list.peek(), Some(&5));
+        *list.peek_mut().unwrap() = 50;
+        assert_eq!(list.pop(), Some(50));
+        assert_eq!(list.pop(), Some(4));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+        assert_eq!(list.peek(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn reuses_free_slots() {
+        let mut list = List::new();
+        for i in 0..100 {
+            list.push(i);
+        }
+        let capacity = list.capacity();
+        for _ in 0..1_000 {
+            for _ in 0..50 {
+                list.pop();
+            }
+            for i in 0..50 {
+                list.push(i);
+            }
+        }
+        assert_eq!(list.len(), 100);
+        assert_eq!(list.capacity(), capacity);
+
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.capacity(), capacity);
+        list.shrink_to_fit();
+        assert_eq!(list.capacity(), 0);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list: List<i32> = vec![1, 2, 3].into_iter().collect();
+        // Interleave pops and pushes so list order and slot order differ.
+        list.pop();
+        list.push(0);
+        list.push(-1);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&-1, &0, &2, &3]);
+        assert_eq!(list.iter().len(), 4);
+
+        for elem in &mut list {
+            *elem *= 10;
+        }
+        assert_eq!(format!("{:?}", list), "[-10, 0, 20, 30]");
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![-10, 0, 20, 30]);
+    }
+
+    #[test]
+    fn drops_remaining_elements() {
+        use std::rc::Rc;
+        let shared = Rc::new(());
+        {
+            let mut list = List::new();
+            for _ in 0..10 {
+                list.push(shared.clone());
+            }
+            list.pop();
+            assert_eq!(Rc::strong_count(&shared), 10);
+        }
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+}
@@ -1,46 +1,304 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
 use std::mem;
 
-pub struct List {
-    head: Link,
+pub struct List<T> {
+    head: Link<T>,
+    len: usize,
 }
 
-enum Link {
+enum Link<T> {
     Empty,
-    More(Box<Node>),
+    More(Box<Node<T>>),
 }
 
-struct Node {
-    elem: i32,
-    next: Link,
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
 }
 
-impl List {
+impl<T> Link<T> {
+    fn as_ref(&self) -> Option<&Node<T>> {
+        match *self {
+            Link::Empty => None,
+            Link::More(ref node) => Some(node),
+        }
+    }
+
+    fn as_mut(&mut self) -> Option<&mut Node<T>> {
+        match *self {
+            Link::Empty => None,
+            Link::More(ref mut node) => Some(node),
+        }
+    }
+}
+
+impl<T> List<T> {
     pub fn new() -> Self {
-        List { head: Link::Empty }
+        List { head: Link::Empty, len: 0 }
     }
 
-    pub fn push(&mut self, elem: i32) {
+    pub fn push(&mut self, elem: T) {
         let new_node = Box::new(Node {
-            elem: elem,
+            elem,
             next: mem::replace(&mut self.head, Link::Empty),
         });
 
         self.head = Link::More(new_node);
+        self.len += 1;
     }
 
-    pub fn pop(&mut self) -> Option<i32> {
+    pub fn pop(&mut self) -> Option<T> {
         match mem::replace(&mut self.head, Link::Empty) {
             Link::Empty => None,
             Link::More(boxed_node) => {
                 let node = *boxed_node;
                 self.head = node.next;
+                self.len -= 1;
                 Some(node.elem)
             }
         }
     }
+
+    /// The element that `pop` would return, without removing it.
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.elem)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates from the head (the most recently pushed element) to the
+    /// tail.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self.head.as_ref(), len: self.len }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { next: self.head.as_mut(), len: self.len }
+    }
+
+    /// Appends `iter` after the last element, walking to the tail once.
+    fn append_iter<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut tail = &mut self.head;
+        while let Link::More(ref mut node) = *tail {
+            tail = &mut node.next;
+        }
+        for elem in iter {
+            *tail = Link::More(Box::new(Node { elem, next: Link::Empty }));
+            self.len += 1;
+            tail = match *tail {
+                Link::More(ref mut node) => &mut node.next,
+                Link::Empty => unreachable!(),
+            };
+        }
+    }
+}
+
+//
+// Bulk operations. All of them relink the existing nodes instead of
+// allocating, and none recurses, so they work on lists of any length.
+//
+
+impl<T> Link<T> {
+    /// Detaches the first node, leaving the rest of the chain in its place.
+    fn pop_node(&mut self) -> Option<Box<Node<T>>> {
+        match mem::replace(self, Link::Empty) {
+            Link::Empty => None,
+            Link::More(mut node) => {
+                *self = mem::replace(&mut node.next, Link::Empty);
+                Some(node)
+            }
+        }
+    }
+}
+
+/// The `next` link of the last node in the chain starting at `link`, or
+/// `link` itself if the chain is empty.
+fn tail_link<T>(mut link: &mut Link<T>) -> &mut Link<T> {
+    while let Link::More(ref mut node) = *link {
+        link = &mut node.next;
+    }
+    link
+}
+
+/// Merges two sorted chains into one. On ties, nodes from `a` come first.
+fn merge_links<T, F>(mut a: Link<T>, mut b: Link<T>, compare: &mut F) -> Link<T>
+    where F: FnMut(&T, &T) -> Ordering
+{
+    let mut head = Link::Empty;
+    {
+        let mut tail = &mut head;
+        loop {
+            let from_b = match (a.as_ref(), b.as_ref()) {
+                (Some(x), Some(y)) => compare(&y.elem, &x.elem) == Ordering::Less,
+                (Some(_), None) | (None, None) => {
+                    *tail = a;
+                    break;
+                }
+                (None, Some(_)) => {
+                    *tail = b;
+                    break;
+                }
+            };
+            let node = if from_b { b.pop_node() } else { a.pop_node() };
+            *tail = Link::More(node.unwrap());
+            tail = match *tail {
+                Link::More(ref mut node) => &mut node.next,
+                Link::Empty => unreachable!(),
+            };
+        }
+    }
+    head
+}
+
+impl<T> List<T> {
+    /// Reverses the list in place.
+    pub fn reverse(&mut self) {
+        let mut reversed = Link::Empty;
+        while let Some(mut node) = self.head.pop_node() {
+            node.next = reversed;
+            reversed = Link::More(node);
+        }
+        self.head = reversed;
+    }
+
+    /// Moves all of `other`'s elements to the end of this list, leaving
+    /// `other` empty.
+    pub fn append(&mut self, other: &mut List<T>) {
+        *tail_link(&mut self.head) = mem::replace(&mut other.head, Link::Empty);
+        self.len += mem::replace(&mut other.len, 0);
+    }
+
+    /// Splits the list in two at `at`: this list keeps the first `at`
+    /// elements and the rest are returned.
+    ///
+    /// Panics if `at > self.len()`.
+    pub fn split_off(&mut self, at: usize) -> List<T> {
+        assert!(at <= self.len, "split index {} out of bounds for length {}", at, self.len);
+        let mut link = &mut self.head;
+        for _ in 0..at {
+            link = match *link {
+                Link::More(ref mut node) => &mut node.next,
+                Link::Empty => unreachable!(),
+            };
+        }
+        let rest = List { head: mem::replace(link, Link::Empty), len: self.len - at };
+        self.len = at;
+        rest
+    }
+
+    /// Keeps only the elements for which `keep` returns `true`, in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let mut link = &mut self.head;
+        loop {
+            let kept = match *link {
+                Link::More(ref node) => keep(&node.elem),
+                Link::Empty => break,
+            };
+            if kept {
+                link = match *link {
+                    Link::More(ref mut node) => &mut node.next,
+                    Link::Empty => unreachable!(),
+                };
+            } else {
+                link.pop_node();
+                self.len -= 1;
+            }
+        }
+    }
+
+    /// Sorts the list with a stable merge sort, relinking nodes in place.
+    ///
+    /// This is the bottom-up variant: runs of length 2^i are kept in
+    /// `runs[i]` and merged like a binary counter, which needs only
+    /// O(log n) extra space and no recursion.
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        // runs[i] is empty or holds 2^i sorted elements, which all came
+        // before those in runs[i - 1].
+        let mut runs: Vec<Link<T>> = Vec::new();
+        while let Some(node) = self.head.pop_node() {
+            let mut carry = Link::More(node);
+            let mut i = 0;
+            while i < runs.len() {
+                match runs[i] {
+                    Link::Empty => break,
+                    Link::More(_) => {
+                        let earlier = mem::replace(&mut runs[i], Link::Empty);
+                        carry = merge_links(earlier, carry, &mut compare);
+                        i += 1;
+                    }
+                }
+            }
+            if i == runs.len() {
+                runs.push(carry);
+            } else {
+                runs[i] = carry;
+            }
+        }
+
+        let mut sorted = Link::Empty;
+        for run in runs {
+            sorted = merge_links(run, sorted, &mut compare);
+        }
+        self.head = sorted;
+    }
+}
+
+impl<T: Ord> List<T> {
+    pub fn sort(&mut self) {
+        self.sort_by(|a, b| a.cmp(b));
+    }
+
+    /// Merges the sorted list `other` into this sorted list, keeping it
+    /// sorted. Equal elements from this list come first.
+    pub fn merge(&mut self, mut other: List<T>) {
+        let mine = mem::replace(&mut self.head, Link::Empty);
+        let theirs = mem::replace(&mut other.head, Link::Empty);
+        self.head = merge_links(mine, theirs, &mut |a: &T, b: &T| a.cmp(b));
+        self.len += mem::replace(&mut other.len, 0);
+    }
+}
+
+impl<T: PartialEq> List<T> {
+    /// Removes consecutive repeated elements, like `Vec::dedup`.
+    pub fn dedup(&mut self) {
+        let mut link = &mut self.head;
+        while let Link::More(ref mut node) = *link {
+            loop {
+                let repeated = match node.next {
+                    Link::More(ref next) => next.elem == node.elem,
+                    Link::Empty => false,
+                };
+                if !repeated {
+                    break;
+                }
+                node.next.pop_node();
+                self.len -= 1;
+            }
+            link = &mut node.next;
+        }
+    }
 }
 
-impl Drop for List {
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+impl<T> Drop for List<T> {
     fn drop(&mut self) {
         let mut cur_link = mem::replace(&mut self.head, Link::Empty);
         // `while let` == "do this thing until this pattern doesn't match"
@@ -53,9 +311,147 @@ impl Drop for List {
     }
 }
 
+/// Collects in iteration order: the first element yielded becomes the head.
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.append_iter(iter);
+        list
+    }
+}
+
+/// Appends at the tail, like `Vec::extend`, so that `iter` order is kept.
+/// Use `push` to add elements at the head instead.
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.append_iter(iter);
+    }
+}
+
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Hash> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for elem in self {
+            elem.hash(state);
+        }
+    }
+}
+
+pub struct IntoIter<T>(List<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+pub struct Iter<'a, T: 'a> {
+    next: Option<&'a Node<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.map(|node| {
+            self.next = node.next.as_ref();
+            self.len -= 1;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+pub struct IterMut<'a, T: 'a> {
+    next: Option<&'a mut Node<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.next.take().map(|node| {
+            self.next = node.next.as_mut();
+            self.len -= 1;
+            &mut node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::List;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
     #[test]
     fn basics() {
         let mut list = List::new();
@@ -84,4 +480,222 @@ mod test {
         assert_eq!(list.pop(), Some(1));
         assert_eq!(list.pop(), None);
     }
+
+    #[test]
+    fn peek() {
+        let mut list = List::new();
+        assert_eq!(list.peek(), None);
+        assert_eq!(list.peek_mut(), None);
+        list.push(1);
+        list.push(2);
+
+        assert_eq!(list.peek(), Some(&2));
+        if let Some(value) = list.peek_mut() {
+            *value = 42;
+        }
+        assert_eq!(list.peek(), Some(&42));
+        assert_eq!(list.pop(), Some(42));
+    }
+
+    #[test]
+    fn len() {
+        let mut list = List::new();
+        assert!(list.is_empty());
+        list.push("a");
+        list.push("b");
+        assert_eq!(list.len(), 2);
+        list.pop();
+        list.pop();
+        list.pop();
+        assert_eq!(list.len(), 0);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list = List::new();
+        list.push(1);
+        list.push(2);
+        list.push(3);
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), None);
+
+        for value in &mut list {
+            *value *= 10;
+        }
+        assert_eq!(list.iter().cloned().collect::<Vec<_>>(), vec![30, 20, 10]);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![30, 20, 10]);
+    }
+
+    #[test]
+    fn from_iter_and_extend() {
+        let mut list: List<_> = vec![1, 2, 3].into_iter().collect();
+        assert_eq!(list.peek(), Some(&1));
+        list.extend(vec![4, 5]);
+        list.push(0);
+        assert_eq!(list.len(), 6);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+
+        let mut empty = List::new();
+        empty.extend(Some("x"));
+        assert_eq!(empty.iter().collect::<Vec<_>>(), vec![&"x"]);
+    }
+
+    #[test]
+    fn clone_eq_hash_debug() {
+        fn hash<T: Hash>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let list: List<String> = vec!["a", "b"].into_iter().map(String::from).collect();
+        let copy = list.clone();
+        assert_eq!(copy, list);
+        assert_eq!(hash(&copy), hash(&list));
+        assert_eq!(format!("{:?}", list), "[\"a\", \"b\"]");
+
+        let mut other = list.clone();
+        other.pop();
+        assert!(other != list);
+        assert_eq!(format!("{:?}", List::<i32>::new()), "[]");
+    }
+
+    #[test]
+    fn long_list() {
+        // Neither dropping nor cloning recurses once per node.
+        let list: List<u32> = (0..1_000_000).collect();
+        let copy = list.clone();
+        assert_eq!(copy.len(), 1_000_000);
+        assert!(copy == list);
+    }
+
+    fn list(elems: &[i32]) -> List<i32> {
+        elems.iter().cloned().collect()
+    }
+
+    fn contents<T: Clone>(list: &List<T>) -> Vec<T> {
+        let elems: Vec<T> = list.iter().cloned().collect();
+        assert_eq!(elems.len(), list.len());
+        elems
+    }
+
+    #[test]
+    fn reverse() {
+        let mut l = list(&[1, 2, 3]);
+        l.reverse();
+        assert_eq!(contents(&l), vec![3, 2, 1]);
+        l.push(4);
+        assert_eq!(contents(&l), vec![4, 3, 2, 1]);
+
+        let mut empty = List::<i32>::new();
+        empty.reverse();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn append_and_split_off() {
+        let mut l = list(&[1, 2]);
+        let mut other = list(&[3, 4, 5]);
+        l.append(&mut other);
+        assert_eq!(contents(&l), vec![1, 2, 3, 4, 5]);
+        assert!(other.is_empty());
+        other.append(&mut l);
+        assert_eq!(contents(&other), vec![1, 2, 3, 4, 5]);
+
+        let rest = other.split_off(2);
+        assert_eq!(contents(&other), vec![1, 2]);
+        assert_eq!(contents(&rest), vec![3, 4, 5]);
+        assert!(other.split_off(2).is_empty());
+        let all = other.split_off(0);
+        assert!(other.is_empty());
+        assert_eq!(contents(&all), vec![1, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_out_of_bounds() {
+        list(&[1, 2]).split_off(3);
+    }
+
+    #[test]
+    fn retain() {
+        let mut l = list(&[1, 2, 3, 4, 5, 6, 7]);
+        l.retain(|&x| x % 3 != 1);
+        assert_eq!(contents(&l), vec![2, 3, 5, 6]);
+        l.retain(|_| false);
+        assert!(l.is_empty());
+    }
+
+    #[test]
+    fn dedup() {
+        let mut l = list(&[1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        l.dedup();
+        assert_eq!(contents(&l), vec![1, 2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn sort() {
+        let mut l = list(&[5, 3, 9, 1, 3, 7, 0, 2, 8]);
+        l.sort();
+        assert_eq!(contents(&l), vec![0, 1, 2, 3, 3, 5, 7, 8, 9]);
+
+        // Compared to Vec::sort on pseudo-random input of every length up
+        // to a few runs' worth.
+        let mut x = 12345u32;
+        for n in 0..70 {
+            let elems: Vec<i32> = (0..n).map(|_| {
+                x = x.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (x >> 16) as i32 % 50
+            }).collect();
+            let mut l = list(&elems);
+            l.sort();
+            let mut expected = elems.clone();
+            expected.sort();
+            assert_eq!(contents(&l), expected);
+        }
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let pairs: Vec<(i32, usize)> = (0..1000).map(|i| ((i * 7 % 10) as i32, i)).collect();
+        let mut l: List<_> = pairs.iter().cloned().collect();
+        l.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut expected = pairs.clone();
+        expected.sort_by_key(|p| p.0);
+        assert_eq!(contents(&l), expected);
+    }
+
+    #[test]
+    fn merge() {
+        let mut l = list(&[1, 3, 5, 7]);
+        l.merge(list(&[0, 3, 4, 8, 9]));
+        assert_eq!(contents(&l), vec![0, 1, 3, 3, 4, 5, 7, 8, 9]);
+        l.merge(List::new());
+        assert_eq!(l.len(), 9);
+
+        let mut empty = List::new();
+        empty.merge(list(&[2]));
+        assert_eq!(contents(&empty), vec![2]);
+    }
+
+    #[test]
+    fn bulk_operations_on_long_list() {
+        let n = 1_000_000;
+        let mut l: List<u32> = (0..n).collect();
+        l.reverse();
+        l.sort();
+        assert_eq!(l.peek(), Some(&0));
+        let mut rest = l.split_off(n as usize / 2);
+        l.retain(|x| x % 2 == 0);
+        l.append(&mut rest);
+        l.dedup();
+        assert_eq!(l.len(), n as usize / 4 + n as usize / 2);
+    }
 }
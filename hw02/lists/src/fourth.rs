@@ -0,0 +1,843 @@
+//! A doubly-linked deque, with a cursor for O(1) edits in the middle.
+//!
+//! Nodes are linked with raw `NonNull` pointers. Every access to a node
+//! goes through `as_ptr()` rather than through a long-lived reference, so
+//! that no two references to the same node are ever live at once; the tests
+//! are meant to pass under Miri (`cargo +nightly miri test`).
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+pub struct Deque<T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    /// We own `T`s, for the drop checker.
+    _marker: PhantomData<T>,
+}
+
+type NodePtr<T> = NonNull<Node<T>>;
+type Link<T> = Option<NodePtr<T>>;
+
+struct Node<T> {
+    /// Towards the front of the deque.
+    prev: Link<T>,
+    /// Towards the back of the deque.
+    next: Link<T>,
+    elem: T,
+}
+
+fn new_node<T>(elem: T) -> NodePtr<T> {
+    let node = Box::new(Node { prev: None, next: None, elem });
+    // Box::into_raw never returns null.
+    unsafe { NonNull::new_unchecked(Box::into_raw(node)) }
+}
+
+impl<T> Deque<T> {
+    pub fn new() -> Self {
+        Deque { front: None, back: None, len: 0, _marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let node = new_node(elem);
+        unsafe {
+            match self.front {
+                Some(old) => {
+                    (*old.as_ptr()).prev = Some(node);
+                    (*node.as_ptr()).next = Some(old);
+                }
+                None => self.back = Some(node),
+            }
+        }
+        self.front = Some(node);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let node = new_node(elem);
+        unsafe {
+            match self.back {
+                Some(old) => {
+                    (*old.as_ptr()).next = Some(node);
+                    (*node.as_ptr()).prev = Some(old);
+                }
+                None => self.front = Some(node),
+            }
+        }
+        self.back = Some(node);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.front.map(|node| unsafe {
+            let boxed = Box::from_raw(node.as_ptr());
+            self.front = boxed.next;
+            match self.front {
+                Some(new) => (*new.as_ptr()).prev = None,
+                None => self.back = None,
+            }
+            self.len -= 1;
+            boxed.elem
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.back.map(|node| unsafe {
+            let boxed = Box::from_raw(node.as_ptr());
+            self.back = boxed.prev;
+            match self.back {
+                Some(new) => (*new.as_ptr()).next = None,
+                None => self.front = None,
+            }
+            self.len -= 1;
+            boxed.elem
+        })
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        self.front.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        self.front.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        self.back.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        self.back.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { front: self.front, back: self.back, len: self.len, _marker: PhantomData }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { front: self.front, back: self.back, len: self.len, _marker: PhantomData }
+    }
+
+    /// A cursor starting at the "ghost" position, just past the back and
+    /// just before the front.
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { cur: None, list: self, index: None }
+    }
+
+    /// Moves every element of `other` to the back of this deque.
+    pub fn append(&mut self, other: &mut Deque<T>) {
+        self.cursor_mut().splice_before(::std::mem::take(other));
+    }
+}
+
+impl<T> Default for Deque<T> {
+    fn default() -> Self {
+        Deque::new()
+    }
+}
+
+impl<T> Drop for Deque<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> FromIterator<T> for Deque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Deque::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+/// Pushes to the back.
+impl<T> Extend<T> for Deque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T: Clone> Clone for Deque<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Deque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for Deque<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Deque<T> {}
+
+impl<T: Hash> Hash for Deque<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for elem in self {
+            elem.hash(state);
+        }
+    }
+}
+
+// A deque owns its elements outright, just like a `Vec`.
+unsafe impl<T: Send> Send for Deque<T> {}
+unsafe impl<T: Sync> Sync for Deque<T> {}
+
+pub struct Iter<'a, T: 'a> {
+    front: Link<T>,
+    back: Link<T>,
+    /// Elements left between `front` and `back`, inclusive. The ends never
+    /// cross because iteration stops when this hits zero.
+    len: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| unsafe {
+            self.len -= 1;
+            self.front = (*node.as_ptr()).next;
+            &(*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| unsafe {
+            self.len -= 1;
+            self.back = (*node.as_ptr()).prev;
+            &(*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+unsafe impl<'a, T: Sync> Send for Iter<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+
+pub struct IterMut<'a, T: 'a> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| unsafe {
+            self.len -= 1;
+            self.front = (*node.as_ptr()).next;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| unsafe {
+            self.len -= 1;
+            self.back = (*node.as_ptr()).prev;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a mut Deque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+unsafe impl<'a, T: Send> Send for IterMut<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for IterMut<'a, T> {}
+
+pub struct IntoIter<T>(Deque<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+/// A position in a `Deque` that can edit the list around it.
+///
+/// The cursor is either on an element or on the "ghost" position between
+/// the back and the front, so moving past either end wraps around through
+/// the ghost.
+pub struct CursorMut<'a, T: 'a> {
+    cur: Link<T>,
+    list: &'a mut Deque<T>,
+    /// Index of `cur`, or `None` at the ghost.
+    index: Option<usize>,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// Moves towards the back; from the back element onto the ghost, and
+    /// from the ghost onto the front element.
+    pub fn move_next(&mut self) {
+        match self.cur {
+            Some(cur) => unsafe {
+                self.cur = (*cur.as_ptr()).next;
+                self.index = match self.cur {
+                    Some(_) => self.index.map(|i| i + 1),
+                    None => None,
+                };
+            },
+            None if !self.list.is_empty() => {
+                self.cur = self.list.front;
+                self.index = Some(0);
+            }
+            None => {}
+        }
+    }
+
+    /// Moves towards the front; from the front element onto the ghost, and
+    /// from the ghost onto the back element.
+    pub fn move_prev(&mut self) {
+        match self.cur {
+            Some(cur) => unsafe {
+                self.cur = (*cur.as_ptr()).prev;
+                self.index = match self.cur {
+                    Some(_) => self.index.map(|i| i - 1),
+                    None => None,
+                };
+            },
+            None if !self.list.is_empty() => {
+                self.cur = self.list.back;
+                self.index = Some(self.list.len - 1);
+            }
+            None => {}
+        }
+    }
+
+    /// The element under the cursor, or `None` at the ghost.
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.cur.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = match self.cur {
+            Some(cur) => unsafe { (*cur.as_ptr()).next },
+            None => self.list.front,
+        };
+        next.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let prev = match self.cur {
+            Some(cur) => unsafe { (*cur.as_ptr()).prev },
+            None => self.list.back,
+        };
+        prev.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    /// Inserts `elem` just before the cursor (at the back, from the ghost).
+    pub fn insert_before(&mut self, elem: T) {
+        let mut single = Deque::new();
+        single.push_back(elem);
+        self.splice_before(single);
+    }
+
+    /// Inserts `elem` just after the cursor (at the front, from the ghost).
+    pub fn insert_after(&mut self, elem: T) {
+        let mut single = Deque::new();
+        single.push_back(elem);
+        self.splice_after(single);
+    }
+
+    /// Removes the element under the cursor and moves onto the next one.
+    /// Does nothing at the ghost.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let cur = self.cur?;
+        unsafe {
+            let boxed = Box::from_raw(cur.as_ptr());
+            match boxed.prev {
+                Some(prev) => (*prev.as_ptr()).next = boxed.next,
+                None => self.list.front = boxed.next,
+            }
+            match boxed.next {
+                Some(next) => (*next.as_ptr()).prev = boxed.prev,
+                None => {
+                    self.list.back = boxed.prev;
+                    self.index = None;
+                }
+            }
+            self.cur = boxed.next;
+            self.list.len -= 1;
+            Some(boxed.elem)
+        }
+    }
+
+    /// Splits off and returns everything before the cursor. At the ghost,
+    /// that is the whole list.
+    pub fn split_before(&mut self) -> Deque<T> {
+        let (cur, index) = match (self.cur, self.index) {
+            (Some(cur), Some(index)) => (cur, index),
+            _ => return ::std::mem::take(self.list),
+        };
+        let prev = match unsafe { (*cur.as_ptr()).prev } {
+            Some(prev) => prev,
+            None => return Deque::new(),
+        };
+        unsafe {
+            (*cur.as_ptr()).prev = None;
+            (*prev.as_ptr()).next = None;
+        }
+        let before = Deque { front: self.list.front, back: Some(prev), len: index,
+                             _marker: PhantomData };
+        self.list.front = Some(cur);
+        self.list.len -= index;
+        self.index = Some(0);
+        before
+    }
+
+    /// Splits off and returns everything after the cursor. At the ghost,
+    /// that is the whole list.
+    pub fn split_after(&mut self) -> Deque<T> {
+        let (cur, index) = match (self.cur, self.index) {
+            (Some(cur), Some(index)) => (cur, index),
+            _ => return ::std::mem::take(self.list),
+        };
+        let next = match unsafe { (*cur.as_ptr()).next } {
+            Some(next) => next,
+            None => return Deque::new(),
+        };
+        unsafe {
+            (*cur.as_ptr()).next = None;
+            (*next.as_ptr()).prev = None;
+        }
+        let after_len = self.list.len - index - 1;
+        let after = Deque { front: Some(next), back: self.list.back, len: after_len,
+                            _marker: PhantomData };
+        self.list.back = Some(cur);
+        self.list.len -= after_len;
+        after
+    }
+
+    /// Moves every element of `input` to just before the cursor (to the
+    /// back of the list, from the ghost). The cursor stays on the same
+    /// element.
+    pub fn splice_before(&mut self, mut input: Deque<T>) {
+        let (in_front, in_back) = match take_links(&mut input) {
+            Some(links) => links,
+            None => return,
+        };
+        let in_len = input.len;
+        input.len = 0;
+
+        unsafe {
+            // The node the input goes after, and the one it goes before.
+            let (prev, next) = match self.cur {
+                Some(cur) => ((*cur.as_ptr()).prev, Some(cur)),
+                None => (self.list.back, None),
+            };
+            link_between(self.list, prev, next, in_front, in_back);
+        }
+        self.list.len += in_len;
+        if let Some(ref mut index) = self.index {
+            *index += in_len;
+        }
+    }
+
+    /// Moves every element of `input` to just after the cursor (to the
+    /// front of the list, from the ghost). The cursor stays on the same
+    /// element.
+    pub fn splice_after(&mut self, mut input: Deque<T>) {
+        let (in_front, in_back) = match take_links(&mut input) {
+            Some(links) => links,
+            None => return,
+        };
+        let in_len = input.len;
+        input.len = 0;
+
+        unsafe {
+            let (prev, next) = match self.cur {
+                Some(cur) => (Some(cur), (*cur.as_ptr()).next),
+                None => (None, self.list.front),
+            };
+            link_between(self.list, prev, next, in_front, in_back);
+        }
+        self.list.len += in_len;
+    }
+}
+
+/// Detaches the nodes of a non-empty `list`, returning its front and back.
+/// The caller takes over the nodes and must fix up `list.len`.
+fn take_links<T>(list: &mut Deque<T>) -> Option<(NodePtr<T>, NodePtr<T>)> {
+    match (list.front.take(), list.back.take()) {
+        (Some(front), Some(back)) => Some((front, back)),
+        _ => None,
+    }
+}
+
+/// Links the chain `in_front..=in_back` into `list` between the adjacent
+/// nodes `prev` and `next`, where `None` stands for the ends of the list.
+unsafe fn link_between<T>(list: &mut Deque<T>, prev: Link<T>, next: Link<T>,
+                          in_front: NodePtr<T>, in_back: NodePtr<T>) {
+    match prev {
+        Some(prev) => {
+            (*prev.as_ptr()).next = Some(in_front);
+            (*in_front.as_ptr()).prev = Some(prev);
+        }
+        None => list.front = Some(in_front),
+    }
+    match next {
+        Some(next) => {
+            (*next.as_ptr()).prev = Some(in_back);
+            (*in_back.as_ptr()).next = Some(next);
+        }
+        None => list.back = Some(in_back),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Deque;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn deque(elems: &[i32]) -> Deque<i32> {
+        elems.iter().cloned().collect()
+    }
+
+    fn contents(deque: &Deque<i32>) -> Vec<i32> {
+        let forward: Vec<i32> = deque.iter().cloned().collect();
+        let mut backward: Vec<i32> = deque.iter().rev().cloned().collect();
+        backward.reverse();
+        assert_eq!(forward, backward, "links disagree");
+        assert_eq!(forward.len(), deque.len());
+        forward
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = Deque::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.push_front(1);
+        list.push_front(2);
+        list.push_back(3);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.peek_front(), Some(&2));
+        assert_eq!(list.peek_back(), Some(&3));
+
+        *list.peek_front_mut().unwrap() = 20;
+        *list.peek_back_mut().unwrap() = 30;
+        assert_eq!(list.pop_front(), Some(20));
+        assert_eq!(list.pop_back(), Some(30));
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.pop_front(), None);
+        assert!(list.is_empty());
+
+        list.push_back(4);
+        assert_eq!(list.peek_front(), list.peek_back());
+        assert_eq!(list.pop_front(), Some(4));
+        assert_eq!(list.peek_back(), None);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut list = deque(&[1, 2, 3, 4, 5]);
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.collect::<Vec<_>>(), vec![&2, &3, &4]);
+
+        // Mutable references handed out from both ends are all distinct.
+        {
+            let mut iter = list.iter_mut();
+            let a = iter.next().unwrap();
+            let b = iter.next_back().unwrap();
+            let c = iter.next().unwrap();
+            let d = iter.next_back().unwrap();
+            let e = iter.next().unwrap();
+            assert!(iter.next().is_none() && iter.next_back().is_none());
+            *a += 10;
+            *b += 10;
+            *c += 10;
+            *d += 10;
+            *e += 10;
+        }
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), vec![15, 14, 13, 12, 11]);
+    }
+
+    #[test]
+    fn traits() {
+        let list = deque(&[1, 2, 3]);
+        let copy = list.clone();
+        assert_eq!(copy, list);
+        assert_eq!(format!("{:?}", copy), "[1, 2, 3]");
+        assert!(copy != deque(&[1, 2]));
+
+        let mut other = deque(&[4]);
+        let mut list = list;
+        list.append(&mut other);
+        assert_eq!(contents(&list), vec![1, 2, 3, 4]);
+        assert!(other.is_empty());
+    }
+
+    #[test]
+    fn cursor_moves_through_ghost() {
+        let mut list = deque(&[1, 2, 3]);
+        let mut cursor = list.cursor_mut();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 1));
+        assert_eq!(cursor.peek_prev(), Some(&mut 3));
+
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_prev(), None);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.peek_next(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&mut 3));
+        *cursor.current().unwrap() = 30;
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(1));
+
+        let mut empty: Deque<i32> = Deque::new();
+        let mut cursor = empty.cursor_mut();
+        cursor.move_next();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(contents(&list), vec![1, 2, 30]);
+    }
+
+    #[test]
+    fn cursor_insert_remove() {
+        let mut list = deque(&[1, 3]);
+        {
+            let mut cursor = list.cursor_mut();
+            cursor.insert_before(5); // from the ghost: at the back
+            cursor.insert_after(0); // from the ghost: at the front
+            cursor.move_next();
+            cursor.move_next();
+            assert_eq!(cursor.current(), Some(&mut 1));
+            cursor.insert_after(2);
+            cursor.insert_before(-1);
+            assert_eq!(cursor.index(), Some(2));
+            assert_eq!(cursor.current(), Some(&mut 1));
+
+            assert_eq!(cursor.remove_current(), Some(1));
+            assert_eq!(cursor.current(), Some(&mut 2));
+            assert_eq!(cursor.index(), Some(2));
+        }
+        assert_eq!(contents(&list), vec![0, -1, 2, 3, 5]);
+
+        {
+            let mut cursor = list.cursor_mut();
+            // Remove the back, landing on the ghost.
+            cursor.move_prev();
+            assert_eq!(cursor.remove_current(), Some(5));
+            assert_eq!(cursor.index(), None);
+            assert_eq!(cursor.remove_current(), None);
+            // Remove the front.
+            cursor.move_next();
+            assert_eq!(cursor.remove_current(), Some(0));
+            assert_eq!(cursor.index(), Some(0));
+        }
+        assert_eq!(contents(&list), vec![-1, 2, 3]);
+
+        let mut single = deque(&[7]);
+        {
+            let mut cursor = single.cursor_mut();
+            cursor.move_next();
+            assert_eq!(cursor.remove_current(), Some(7));
+        }
+        assert_eq!(contents(&single), vec![]);
+        single.push_back(8);
+        assert_eq!(contents(&single), vec![8]);
+    }
+
+    #[test]
+    fn cursor_split() {
+        let mut list = deque(&[1, 2, 3, 4, 5]);
+        let (before, after) = {
+            let mut cursor = list.cursor_mut();
+            cursor.move_next();
+            cursor.move_next();
+            cursor.move_next();
+            let before = cursor.split_before();
+            assert_eq!(cursor.index(), Some(0));
+            let after = cursor.split_after();
+            assert_eq!(cursor.current(), Some(&mut 3));
+            (before, after)
+        };
+        assert_eq!(contents(&before), vec![1, 2]);
+        assert_eq!(contents(&list), vec![3]);
+        assert_eq!(contents(&after), vec![4, 5]);
+
+        // Splitting at the ends gives empty lists; at the ghost, everything.
+        let mut list = deque(&[1, 2]);
+        {
+            let mut cursor = list.cursor_mut();
+            cursor.move_next();
+            assert_eq!(contents(&cursor.split_before()), vec![]);
+            cursor.move_next();
+            assert_eq!(contents(&cursor.split_after()), vec![]);
+            cursor.move_next();
+            assert_eq!(contents(&cursor.split_after()), vec![1, 2]);
+            assert_eq!(contents(&cursor.split_before()), vec![]);
+        }
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn cursor_splice() {
+        let mut list = deque(&[1, 5]);
+        {
+            let mut cursor = list.cursor_mut();
+            cursor.move_next();
+            cursor.splice_after(deque(&[2, 3]));
+            cursor.move_next();
+            cursor.move_next();
+            cursor.move_next();
+            assert_eq!(cursor.current(), Some(&mut 5));
+            cursor.splice_before(deque(&[4]));
+            assert_eq!(cursor.index(), Some(4));
+            cursor.splice_before(Deque::new());
+            cursor.splice_after(Deque::new());
+
+            // From the ghost, splice_before appends and splice_after prepends.
+            cursor.move_next();
+            cursor.splice_before(deque(&[6, 7]));
+            cursor.splice_after(deque(&[-1, 0]));
+            assert_eq!(cursor.index(), None);
+        }
+        assert_eq!(contents(&list), vec![-1, 0, 1, 2, 3, 4, 5, 6, 7]);
+
+        let mut empty = Deque::new();
+        empty.cursor_mut().splice_after(deque(&[1, 2]));
+        assert_eq!(contents(&empty), vec![1, 2]);
+    }
+
+    #[test]
+    fn drops_every_element_once() {
+        struct Counted(Rc<Cell<usize>>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        {
+            let mut list: Deque<Counted> = (0..10).map(|_| Counted(drops.clone())).collect();
+            let mut cursor = list.cursor_mut();
+            cursor.move_next();
+            cursor.move_next();
+            drop(cursor.remove_current());
+            let tail = cursor.split_after();
+            cursor.splice_before(tail);
+            let head = cursor.split_before();
+            assert_eq!(drops.get(), 1);
+            drop(head);
+            assert_eq!(drops.get(), 9);
+        }
+        assert_eq!(drops.get(), 10);
+    }
+
+    #[test]
+    fn long_list() {
+        let list: Deque<u32> = (0..100_000).collect();
+        assert_eq!(list.iter().next_back(), Some(&99_999));
+    }
+}
@@ -0,0 +1,233 @@
+//! Hazard pointers: safe memory reclamation for lock-free structures.
+//!
+//! A thread that is about to dereference a shared node first publishes the
+//! node's address in a hazard record. A thread that unlinks a node doesn't
+//! free it but *retires* it, and retired nodes are only freed once no hazard
+//! record points at them.
+
+use std::collections::HashSet;
+use std::ptr;
+use std::sync::atomic::{self, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+/// A set of hazard records and the nodes retired against them. Each
+/// lock-free structure owns one.
+pub struct Domain {
+    /// Records are never freed before the domain, only reused.
+    records: AtomicPtr<Record>,
+    num_records: AtomicUsize,
+    retired: AtomicPtr<Retired>,
+    num_retired: AtomicUsize,
+}
+
+struct Record {
+    /// The address this record protects, or null.
+    hazard: AtomicPtr<()>,
+    /// Whether a `Guard` currently owns this record.
+    active: AtomicBool,
+    next: *mut Record,
+}
+
+struct Retired {
+    ptr: *mut (),
+    free: unsafe fn(*mut ()),
+    next: *mut Retired,
+}
+
+/// Retired nodes are scanned once there are this many more of them than
+/// there are hazard records, so each scan frees at least this many.
+const SCAN_SLACK: usize = 64;
+
+unsafe fn free_box<T>(ptr: *mut ()) {
+    drop(Box::from_raw(ptr as *mut T));
+}
+
+impl Domain {
+    pub fn new() -> Self {
+        Domain {
+            records: AtomicPtr::new(ptr::null_mut()),
+            num_records: AtomicUsize::new(0),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            num_retired: AtomicUsize::new(0),
+        }
+    }
+
+    /// Claims a hazard record, reusing an idle one if there is one.
+    pub fn guard(&self) -> Guard<'_> {
+        let mut cur = self.records.load(Ordering::Acquire);
+        while !cur.is_null() {
+            let record = unsafe { &*cur };
+            if !record.active.load(Ordering::Relaxed)
+                && record.active.compare_exchange(false, true, Ordering::Acquire,
+                                                  Ordering::Relaxed).is_ok() {
+                return Guard { record };
+            }
+            cur = record.next;
+        }
+
+        let record = Box::into_raw(Box::new(Record {
+            hazard: AtomicPtr::new(ptr::null_mut()),
+            active: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.records.load(Ordering::Relaxed);
+        loop {
+            // Not shared until the exchange succeeds.
+            unsafe { (*record).next = head };
+            match self.records.compare_exchange_weak(head, record, Ordering::Release,
+                                                     Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(actual) => head = actual,
+            }
+        }
+        self.num_records.fetch_add(1, Ordering::Relaxed);
+        Guard { record: unsafe { &*record } }
+    }
+
+    /// Frees `ptr` (a `Box<T>` turned raw) once no guard protects it.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `Box::into_raw`, be retired only once, and
+    /// already be unreachable for threads that don't yet protect it.
+    pub unsafe fn retire<T>(&self, ptr: *mut T) {
+        let retired = Box::into_raw(Box::new(Retired {
+            ptr: ptr as *mut (),
+            free: free_box::<T>,
+            next: ptr::null_mut(),
+        }));
+        // Count the node before publishing it, so a concurrent scan that
+        // frees it can't take the count below zero.
+        let retired_count = self.num_retired.fetch_add(1, Ordering::Relaxed) + 1;
+        self.push_retired(retired, retired);
+
+        if retired_count >= self.num_records.load(Ordering::Relaxed) + SCAN_SLACK {
+            self.scan();
+        }
+    }
+
+    /// Pushes the chain `first..=last` of retired nodes.
+    fn push_retired(&self, first: *mut Retired, last: *mut Retired) {
+        let mut head = self.retired.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*last).next = head };
+            match self.retired.compare_exchange_weak(head, first, Ordering::Release,
+                                                     Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(actual) => head = actual,
+            }
+        }
+    }
+
+    /// Frees every retired node that no guard protects.
+    fn scan(&self) {
+        // Taking the whole list gives this thread sole ownership of it.
+        let mut cur = self.retired.swap(ptr::null_mut(), Ordering::Acquire);
+        if cur.is_null() {
+            return;
+        }
+
+        // Pairs with the fence in `Guard::protect`: either the protecting
+        // thread sees that the node was unlinked and retries, or we see its
+        // hazard.
+        atomic::fence(Ordering::SeqCst);
+        let mut hazards = HashSet::new();
+        let mut record = self.records.load(Ordering::Acquire);
+        while !record.is_null() {
+            let r = unsafe { &*record };
+            let hazard = r.hazard.load(Ordering::SeqCst);
+            if !hazard.is_null() {
+                hazards.insert(hazard);
+            }
+            record = r.next;
+        }
+
+        let (mut kept_first, mut kept_last): (*mut Retired, *mut Retired) =
+            (ptr::null_mut(), ptr::null_mut());
+        let mut freed = 0;
+        while !cur.is_null() {
+            let retired = cur;
+            unsafe {
+                cur = (*retired).next;
+                if hazards.contains(&(*retired).ptr) {
+                    (*retired).next = kept_first;
+                    kept_first = retired;
+                    if kept_last.is_null() {
+                        kept_last = retired;
+                    }
+                } else {
+                    let retired = Box::from_raw(retired);
+                    (retired.free)(retired.ptr);
+                    freed += 1;
+                }
+            }
+        }
+        self.num_retired.fetch_sub(freed, Ordering::Relaxed);
+        if !kept_first.is_null() {
+            self.push_retired(kept_first, kept_last);
+        }
+    }
+
+    /// Number of retired nodes not yet freed.
+    #[cfg(test)]
+    pub fn num_retired(&self) -> usize {
+        self.num_retired.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for Domain {
+    fn default() -> Self {
+        Domain::new()
+    }
+}
+
+impl Drop for Domain {
+    fn drop(&mut self) {
+        // With `&mut self` there are no guards left, so everything goes.
+        let mut retired = *self.retired.get_mut();
+        while !retired.is_null() {
+            let boxed = unsafe { Box::from_raw(retired) };
+            unsafe { (boxed.free)(boxed.ptr) };
+            retired = boxed.next;
+        }
+        let mut record = *self.records.get_mut();
+        while !record.is_null() {
+            let boxed = unsafe { Box::from_raw(record) };
+            record = boxed.next;
+        }
+    }
+}
+
+/// Ownership of one hazard record, released on drop.
+pub struct Guard<'a> {
+    record: &'a Record,
+}
+
+impl<'a> Guard<'a> {
+    /// Loads `src` and protects the loaded pointer, retrying until the
+    /// protection is known to have been published before `src` changed.
+    /// The result can be dereferenced until the next `protect` or `clear`,
+    /// provided whoever unlinks it retires it through the same domain.
+    pub fn protect<T>(&self, src: &AtomicPtr<T>) -> *mut T {
+        let mut ptr = src.load(Ordering::Relaxed);
+        loop {
+            self.record.hazard.store(ptr as *mut (), Ordering::SeqCst);
+            atomic::fence(Ordering::SeqCst);
+            let current = src.load(Ordering::Acquire);
+            if current == ptr {
+                return ptr;
+            }
+            ptr = current;
+        }
+    }
+
+    pub fn clear(&self) {
+        self.record.hazard.store(ptr::null_mut(), Ordering::Release);
+    }
+}
+
+impl<'a> Drop for Guard<'a> {
+    fn drop(&mut self) {
+        self.clear();
+        self.record.active.store(false, Ordering::Release);
+    }
+}
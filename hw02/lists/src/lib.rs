@@ -1,3 +1,9 @@
 pub mod first;
+pub mod third;
+pub mod fourth;
+pub mod treiber;
+pub mod arena;
+pub mod unrolled;
+pub mod skip_map;
 
-
+mod hazard;
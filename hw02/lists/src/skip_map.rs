@@ -0,0 +1,453 @@
+//! An ordered map built on a skip list.
+//!
+//! Every entry is a node in a sorted singly-linked list, and some nodes are
+//! also linked into sparser "express" lists above it: a node of height `h`
+//! sits in levels `0..h`, and each extra level is half as likely as the one
+//! below. Searches start in the sparsest level and drop down a level
+//! whenever the next node would overshoot, so they take O(log n) steps on
+//! average without any rebalancing.
+//!
+//! Nodes are separate heap allocations linked by raw pointers, like
+//! `fourth::Deque`, and an insert or remove only rewrites the links into one
+//! node. That is the shape lock-free skip lists use, with `AtomicPtr` links
+//! and reclamation as in `treiber::Stack`.
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::ptr::{self, NonNull};
+
+/// Tallest a node can be; 2^32 entries before searches slow down.
+const MAX_HEIGHT: usize = 32;
+
+pub struct SkipMap<K, V> {
+    /// Links out of the head, one per level in use.
+    head: Vec<Link<K, V>>,
+    len: usize,
+    /// State of the generator that picks node heights.
+    rng: u64,
+    _marker: PhantomData<Box<Node<K, V>>>,
+}
+
+type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    /// Links to the next node in each level this node is in.
+    next: Box<[Link<K, V>]>,
+}
+
+impl<K: Ord, V> SkipMap<K, V> {
+    /// An empty map whose node heights come from a random seed.
+    pub fn new() -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        SkipMap::with_seed(seed)
+    }
+
+    /// An empty map whose node heights are a fixed function of `seed`, so
+    /// the same seed and the same operations always build the same list.
+    pub fn with_seed(seed: u64) -> Self {
+        SkipMap { head: Vec::new(), len: 0, rng: seed, _marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaces.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        unsafe {
+            let mut links = self.links_to(&key);
+            if let Some(&link) = links.first() {
+                if let Some(node) = *link {
+                    if (*node.as_ptr()).key == key {
+                        return Some(::std::mem::replace(&mut (*node.as_ptr()).value, value));
+                    }
+                }
+            }
+            // Only a node that is actually linked in draws a height, so
+            // replacing values leaves the generator, and the levels in use,
+            // alone.
+            let height = self.random_height();
+            if height > self.head.len() {
+                // Growing `head` may move it, so find the links again.
+                self.head.resize(height, None);
+                links = self.links_to(&key);
+            }
+            let node = Box::new(Node { key, value, next: vec![None; height].into_boxed_slice() });
+            let node = NonNull::new_unchecked(Box::into_raw(node));
+            for (level, &link) in links.iter().enumerate().take(height) {
+                (*node.as_ptr()).next[level] = *link;
+                *link = Some(node);
+            }
+        }
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q: ?Sized + Ord>(&self, key: &Q) -> Option<&V> where K: Borrow<Q> {
+        self.find(key).map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn get_mut<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<&mut V> where K: Borrow<Q> {
+        // Nodes are only reachable through `self`, which stays borrowed
+        // mutably for as long as the result.
+        self.find(key).map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn contains_key<Q: ?Sized + Ord>(&self, key: &Q) -> bool where K: Borrow<Q> {
+        self.find(key).is_some()
+    }
+
+    /// Removes `key`, returning its value if it was present.
+    pub fn remove<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<V> where K: Borrow<Q> {
+        if self.is_empty() {
+            return None;
+        }
+        let node = unsafe {
+            let links = self.links_to(key);
+            let node = match *links[0] {
+                Some(node) if (*node.as_ptr()).key.borrow() == key => node,
+                _ => return None,
+            };
+            let node = Box::from_raw(node.as_ptr());
+            // The node is in exactly the levels below its height, and in
+            // each of them `links` points at the link into it.
+            for (&link, &next) in links.iter().zip(node.next.iter()) {
+                *link = next;
+            }
+            node
+        };
+        while let Some(&None) = self.head.last() {
+            self.head.pop();
+        }
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    /// The entries whose keys fall in `range`, in ascending key order.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+        where Q: ?Sized + Ord, K: Borrow<Q>, R: RangeBounds<Q>
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.first_where(|key| key.borrow() >= start),
+            Bound::Excluded(start) => self.first_where(|key| key.borrow() > start),
+            Bound::Unbounded => self.head.first().cloned().unwrap_or(None),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.first_where(|key| key.borrow() > end),
+            Bound::Excluded(end) => self.first_where(|key| key.borrow() >= end),
+            Bound::Unbounded => None,
+        };
+        // With the bounds the wrong way round, `end` comes before `start`.
+        let start = match (start, end) {
+            (Some(s), Some(e)) if unsafe { (*s.as_ptr()).key >= (*e.as_ptr()).key } => end,
+            _ => start,
+        };
+        Range { next: start, end, _marker: PhantomData }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { range: self.range::<K, _>(..), len: self.len }
+    }
+
+    /// Picks the height of a new node: `h` with probability 2^-h.
+    fn random_height(&mut self) -> usize {
+        // SplitMix64.
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        1 + (z.trailing_ones() as usize).min(MAX_HEIGHT - 1)
+    }
+
+    fn find<Q: ?Sized + Ord>(&self, key: &Q) -> Link<K, V> where K: Borrow<Q> {
+        let node = self.first_where(|k| k.borrow() >= key)?;
+        if unsafe { (*node.as_ptr()).key.borrow() } == key { Some(node) } else { None }
+    }
+
+    /// The first node whose key satisfies `pred`, which must be false for
+    /// some prefix of the keys and true for the rest.
+    fn first_where<F: Fn(&K) -> bool>(&self, pred: F) -> Link<K, V> {
+        let mut links: &[Link<K, V>] = &self.head;
+        for level in (0..self.head.len()).rev() {
+            while let Some(node) = links[level] {
+                let node = unsafe { &*node.as_ptr() };
+                if pred(&node.key) {
+                    break;
+                }
+                links = &node.next;
+            }
+        }
+        links.first().cloned().unwrap_or(None)
+    }
+
+    /// For each level in use, the link that points at the level's first node
+    /// not less than `key`: where a node with that key is linked in or
+    /// out.
+    unsafe fn links_to<Q: ?Sized + Ord>(&mut self, key: &Q) -> Vec<*mut Link<K, V>>
+        where K: Borrow<Q>
+    {
+        let height = self.head.len();
+        let mut links = vec![ptr::null_mut(); height];
+        let mut tower: *mut Link<K, V> = self.head.as_mut_ptr();
+        for level in (0..height).rev() {
+            while let Some(node) = *tower.add(level) {
+                if (*node.as_ptr()).key.borrow() >= key {
+                    break;
+                }
+                tower = (*node.as_ptr()).next.as_mut_ptr();
+            }
+            links[level] = tower.add(level);
+        }
+        links
+    }
+}
+
+impl<K: Ord, V> Default for SkipMap<K, V> {
+    fn default() -> Self {
+        SkipMap::new()
+    }
+}
+
+impl<K, V> Drop for SkipMap<K, V> {
+    fn drop(&mut self) {
+        // Every node is in level 0, so walking it frees them all.
+        let mut cur = self.head.first().cloned().unwrap_or(None);
+        while let Some(node) = cur {
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            cur = node.next[0];
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = SkipMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for SkipMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// Owns its nodes like a `Box` would, so it is as `Send`/`Sync` as its keys
+// and values.
+unsafe impl<K: Send, V: Send> Send for SkipMap<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for SkipMap<K, V> {}
+
+pub struct Range<'a, K: 'a, V: 'a> {
+    next: Link<K, V>,
+    /// First node past the range, or `None` if it runs to the end.
+    end: Link<K, V>,
+    _marker: PhantomData<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.next == self.end {
+            return None;
+        }
+        self.next.map(|node| {
+            let node = unsafe { &*node.as_ptr() };
+            self.next = node.next[0];
+            (&node.key, &node.value)
+        })
+    }
+}
+
+pub struct Iter<'a, K: 'a, V: 'a> {
+    range: Range<'a, K, V>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let entry = self.range.next()?;
+        self.len -= 1;
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K: Ord, V> IntoIterator for &'a SkipMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SkipMap;
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+    use std::rc::Rc;
+
+    #[test]
+    fn basics() {
+        let mut map = SkipMap::with_seed(1);
+        assert!(map.is_empty());
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.remove(&1), None);
+
+        assert_eq!(map.insert(2, "two"), None);
+        assert_eq!(map.insert(1, "one"), None);
+        assert_eq!(map.insert(3, "three"), None);
+        assert_eq!(map.insert(2, "deux"), Some("two"));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&2), Some(&"deux"));
+        assert!(map.contains_key(&3));
+        assert!(!map.contains_key(&4));
+
+        *map.get_mut(&1).unwrap() = "un";
+        assert_eq!(format!("{:?}", map), r#"{1: "un", 2: "deux", 3: "three"}"#);
+
+        assert_eq!(map.remove(&2), Some("deux"));
+        assert_eq!(map.remove(&2), None);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&1, &"un"), (&3, &"three")]);
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let mut map = SkipMap::with_seed(2);
+        map.insert("b".to_string(), 2);
+        map.insert("a".to_string(), 1);
+        assert_eq!(map.get("a"), Some(&1));
+        assert_eq!(map.remove("b"), Some(2));
+        let from_a = (Bound::Included("a"), Bound::Unbounded);
+        assert_eq!(map.range::<str, _>(from_a).count(), 1);
+    }
+
+    #[test]
+    fn matches_btree_map() {
+        let mut map = SkipMap::with_seed(3);
+        let mut reference = BTreeMap::new();
+        let mut x = 1u32;
+        for _ in 0..if cfg!(miri) { 300 } else { 5_000 } {
+            x = x.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let key = (x >> 16) % 500;
+            if x & 4 == 0 {
+                assert_eq!(map.remove(&key), reference.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, x), reference.insert(key, x));
+            }
+            assert_eq!(map.len(), reference.len());
+        }
+        assert!(map.iter().eq(reference.iter()));
+        assert_eq!(map.iter().len(), reference.len());
+        for key in 0..500 {
+            assert_eq!(map.get(&key), reference.get(&key));
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        let map: SkipMap<i32, ()> = (0..20).map(|k| (k * 2, ())).collect();
+        let keys = |r: Vec<(&i32, &())>| r.into_iter().map(|(&k, _)| k).collect::<Vec<_>>();
+
+        assert_eq!(keys(map.range(4..10).collect()), vec![4, 6, 8]);
+        assert_eq!(keys(map.range(3..=10).collect()), vec![4, 6, 8, 10]);
+        assert_eq!(keys(map.range(..3).collect()), vec![0, 2]);
+        assert_eq!(keys(map.range(35..).collect()), vec![36, 38]);
+        assert_eq!(map.range(..).count(), 20);
+        assert_eq!(map.range(5..5).count(), 0);
+        let (hi, lo) = (10, 4);
+        assert_eq!(map.range(hi..lo).count(), 0);
+        assert_eq!(map.range(100..).count(), 0);
+        assert_eq!(map.range(-10..0).count(), 0);
+    }
+
+    #[test]
+    fn same_seed_same_shape() {
+        let heights = |seed| {
+            let mut map = SkipMap::with_seed(seed);
+            (0..100).map(|k| { map.insert(k, ()); map.head.len() }).collect::<Vec<_>>()
+        };
+        assert_eq!(heights(7), heights(7));
+        assert!(heights(7) != heights(8));
+    }
+
+    /// The height of every node, in order, and the number of levels in use.
+    fn shape<K, V>(map: &SkipMap<K, V>) -> (Vec<usize>, usize) {
+        let mut heights = Vec::new();
+        let mut cur = map.head.first().cloned().unwrap_or(None);
+        while let Some(node) = cur {
+            let next = unsafe { &(*node.as_ptr()).next };
+            heights.push(next.len());
+            cur = next[0];
+        }
+        (heights, map.head.len())
+    }
+
+    #[test]
+    fn replacing_draws_no_height() {
+        let build = |replace| {
+            let mut map = SkipMap::with_seed(9);
+            for k in 0..200 {
+                map.insert(k, 0);
+                if replace {
+                    assert_eq!(map.insert(k, 1), Some(0));
+                }
+            }
+            shape(&map)
+        };
+        let (heights, levels) = build(true);
+        assert_eq!((heights.clone(), levels), build(false));
+        assert_eq!(heights.iter().max(), Some(&levels));
+    }
+
+    #[test]
+    fn drops_every_value_once() {
+        let shared = Rc::new(());
+        {
+            let mut map = SkipMap::with_seed(4);
+            for k in 0..100 {
+                map.insert(k, shared.clone());
+            }
+            map.insert(0, shared.clone());
+            map.remove(&1);
+            assert_eq!(Rc::strong_count(&shared), 100);
+        }
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn long_map() {
+        let n = if cfg!(miri) { 1_000 } else { 1_000_000 };
+        let mut map = SkipMap::with_seed(5);
+        for k in 0..n {
+            map.insert(k, k);
+        }
+        assert_eq!(map.get(&(n - 1)), Some(&(n - 1)));
+        assert!(map.head.len() < 40);
+    }
+}
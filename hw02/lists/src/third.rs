@@ -0,0 +1,238 @@
+//! A persistent stack: every operation returns a new list, and lists built
+//! from a common one share its nodes instead of copying them.
+//!
+//! `rc::List` uses `Rc` and is the one to reach for by default;
+//! `sync::List` is the same list over `Arc`, for sharing between threads.
+
+/// Defines the list over the reference-counted pointer `$ptr`, so the `Rc`
+/// and `Arc` versions can't drift apart.
+macro_rules! persistent_list {
+    ($ptr:ident) => {
+        use std::fmt;
+
+        pub struct List<T> {
+            head: Link<T>,
+        }
+
+        type Link<T> = Option<$ptr<Node<T>>>;
+
+        struct Node<T> {
+            elem: T,
+            next: Link<T>,
+        }
+
+        impl<T> List<T> {
+            pub fn new() -> Self {
+                List { head: None }
+            }
+
+            /// A new list with `elem` in front of this one. Both lists share
+            /// every node of this one.
+            pub fn prepend(&self, elem: T) -> List<T> {
+                List { head: Some($ptr::new(Node { elem, next: self.head.clone() })) }
+            }
+
+            /// This list without its first element, sharing the rest.
+            pub fn tail(&self) -> List<T> {
+                List { head: self.head.as_ref().and_then(|node| node.next.clone()) }
+            }
+
+            pub fn head(&self) -> Option<&T> {
+                self.head.as_ref().map(|node| &node.elem)
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.head.is_none()
+            }
+
+            pub fn iter(&self) -> Iter<'_, T> {
+                Iter { next: self.head.as_deref() }
+            }
+        }
+
+        impl<T> Default for List<T> {
+            fn default() -> Self {
+                List::new()
+            }
+        }
+
+        /// Cheap: shares every node.
+        impl<T> Clone for List<T> {
+            fn clone(&self) -> Self {
+                List { head: self.head.clone() }
+            }
+        }
+
+        impl<T> Drop for List<T> {
+            fn drop(&mut self) {
+                let mut head = self.head.take();
+                // Unlink nodes only while we hold the last reference to
+                // them; a shared node belongs to another list as well, so
+                // it and everything after it must stay. `into_inner` rather
+                // than `try_unwrap`: when two threads drop the last two
+                // owners at once, exactly one of them gets the node.
+                while let Some(node) = head {
+                    match $ptr::into_inner(node) {
+                        Some(mut node) => head = node.next.take(),
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        impl<T: fmt::Debug> fmt::Debug for List<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        pub struct Iter<'a, T: 'a> {
+            next: Option<&'a Node<T>>,
+        }
+
+        impl<'a, T> Iterator for Iter<'a, T> {
+            type Item = &'a T;
+
+            fn next(&mut self) -> Option<&'a T> {
+                self.next.map(|node| {
+                    self.next = node.next.as_deref();
+                    &node.elem
+                })
+            }
+        }
+
+        impl<'a, T> IntoIterator for &'a List<T> {
+            type Item = &'a T;
+            type IntoIter = Iter<'a, T>;
+
+            fn into_iter(self) -> Iter<'a, T> {
+                self.iter()
+            }
+        }
+
+        #[cfg(test)]
+        mod test {
+            use super::List;
+
+            #[test]
+            fn basics() {
+                let list = List::new();
+                assert_eq!(list.head(), None);
+
+                let list = list.prepend(1).prepend(2).prepend(3);
+                assert_eq!(list.head(), Some(&3));
+
+                let list = list.tail();
+                assert_eq!(list.head(), Some(&2));
+
+                let list = list.tail();
+                assert_eq!(list.head(), Some(&1));
+
+                let list = list.tail();
+                assert_eq!(list.head(), None);
+                assert!(list.is_empty());
+
+                // Make sure empty tail works
+                let list = list.tail();
+                assert_eq!(list.head(), None);
+            }
+
+            #[test]
+            fn iter() {
+                let list = List::new().prepend(1).prepend(2).prepend(3);
+                assert_eq!(list.iter().collect::<Vec<_>>(), vec![&3, &2, &1]);
+                assert_eq!(format!("{:?}", list), "[3, 2, 1]");
+            }
+
+            #[test]
+            fn sharing() {
+                let base = List::new().prepend(1).prepend(2);
+                let left = base.prepend(3);
+                let right = base.prepend(4);
+                assert!(::std::ptr::eq(left.tail().head().unwrap(),
+                                       right.tail().head().unwrap()));
+
+                // Dropping one branch leaves the shared part to the others.
+                drop(base);
+                drop(left);
+                assert_eq!(right.iter().collect::<Vec<_>>(), vec![&4, &2, &1]);
+                assert_eq!(right.clone().tail().tail().head(), Some(&1));
+            }
+
+            #[test]
+            fn long_list() {
+                let mut list = List::new();
+                for i in 0..1_000_000 {
+                    list = list.prepend(i);
+                }
+                // Dropping a branch stops at the shared prefix, and dropping
+                // the last owner of a long list doesn't recurse per node.
+                let branch = list.prepend(-1);
+                drop(branch);
+                assert_eq!(list.head(), Some(&999_999));
+                drop(list);
+            }
+        }
+    };
+}
+
+pub mod rc {
+    use std::rc::Rc;
+
+    persistent_list!(Rc);
+}
+
+pub mod sync {
+    use std::sync::Arc;
+
+    persistent_list!(Arc);
+
+    #[cfg(test)]
+    mod thread_test {
+        use super::List;
+        use std::sync::{Arc, Barrier};
+        use std::thread;
+
+        #[test]
+        fn shared_between_threads() {
+            let base = List::new().prepend(1).prepend(2);
+            let handles: Vec<_> = (0..4).map(|i| {
+                let base = base.clone();
+                thread::spawn(move || {
+                    let mine = base.prepend(10 + i);
+                    mine.iter().sum::<i32>()
+                })
+            }).collect();
+
+            let sums: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+            assert_eq!(sums, vec![13, 14, 15, 16]);
+            assert_eq!(base.iter().collect::<Vec<_>>(), vec![&2, &1]);
+        }
+
+        #[test]
+        fn long_list_dropped_concurrently() {
+            let len = if cfg!(miri) { 1_000 } else { 1_000_000 };
+            for _ in 0..if cfg!(miri) { 2 } else { 10 } {
+                let mut list = List::new();
+                for i in 0..len {
+                    list = list.prepend(i);
+                }
+                // Two threads drop the last two owners at the same time;
+                // whichever of them frees the nodes must not recurse.
+                let barrier = Arc::new(Barrier::new(2));
+                let handles: Vec<_> = vec![list.clone(), list].into_iter().map(|list| {
+                    let barrier = barrier.clone();
+                    thread::spawn(move || {
+                        barrier.wait();
+                        drop(list);
+                    })
+                }).collect();
+                for handle in handles {
+                    handle.join().unwrap();
+                }
+            }
+        }
+    }
+}
+
+pub use self::rc::List;
@@ -0,0 +1,217 @@
+//! A lock-free stack (Treiber's algorithm) that threads can share without a
+//! `Mutex`.
+//!
+//! The layout is `first::List`'s, a singly-linked list of heap nodes, but
+//! the head is an `AtomicPtr` swapped with compare-and-exchange. Popped
+//! nodes are freed through hazard pointers, so a thread that is still
+//! looking at a node never sees it freed, and a freed node's address
+//! coming back (the ABA problem) can't corrupt the list.
+
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use hazard::Domain;
+
+pub struct Stack<T> {
+    head: AtomicPtr<Node<T>>,
+    domain: Domain,
+}
+
+struct Node<T> {
+    /// Moved out by whichever `pop` unlinks the node, so the node itself
+    /// must never drop it.
+    elem: ManuallyDrop<T>,
+    next: *mut Node<T>,
+}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Stack { head: AtomicPtr::new(ptr::null_mut()), domain: Domain::new() }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Box::into_raw(Box::new(Node {
+            elem: ManuallyDrop::new(elem),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // The node isn't shared until the exchange succeeds.
+            unsafe { (*node).next = head };
+            match self.head.compare_exchange_weak(head, node, Ordering::Release,
+                                                  Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(actual) => head = actual,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = self.domain.guard();
+        loop {
+            let head = guard.protect(&self.head);
+            if head.is_null() {
+                return None;
+            }
+            // `head` is protected, so it can't be freed under us, and a
+            // node's `next` never changes once it has been pushed.
+            let next = unsafe { (*head).next };
+            if self.head.compare_exchange(head, next, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok() {
+                guard.clear();
+                unsafe {
+                    // Only the thread that unlinked the node gets here.
+                    let elem = ptr::read(&*(*head).elem);
+                    self.domain.retire(head);
+                    return Some(elem);
+                }
+            }
+        }
+    }
+
+    /// Whether the stack was empty at the moment of the call; another
+    /// thread may have pushed or popped since.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Stack::new()
+    }
+}
+
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        // Iterative, like `first::List`. Nobody else can be popping, so the
+        // nodes can be freed directly rather than retired.
+        let mut cur = *self.head.get_mut();
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            unsafe { ManuallyDrop::drop(&mut node.elem) };
+            cur = node.next;
+        }
+    }
+}
+
+// Elements move between threads through the stack, but are never shared
+// between them, so `T: Send` is enough for both.
+unsafe impl<T: Send> Send for Stack<T> {}
+unsafe impl<T: Send> Sync for Stack<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::Stack;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let stack = Stack::new();
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    fn reclaims_nodes() {
+        let stack = Stack::new();
+        for i in 0..1_000 {
+            stack.push(i);
+            assert_eq!(stack.pop(), Some(i));
+        }
+        // Unprotected nodes are freed in batches rather than piling up.
+        assert!(stack.domain.num_retired() < 100, "{}", stack.domain.num_retired());
+    }
+
+    #[test]
+    fn drops_every_element_once() {
+        struct Counted(Arc<AtomicUsize>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        {
+            let stack = Stack::new();
+            for _ in 0..200 {
+                stack.push(Counted(drops.clone()));
+            }
+            for _ in 0..150 {
+                drop(stack.pop());
+            }
+            assert_eq!(drops.load(Ordering::Relaxed), 150);
+        }
+        assert_eq!(drops.load(Ordering::Relaxed), 200);
+    }
+
+    #[test]
+    fn long_stack() {
+        let stack = Stack::new();
+        for i in 0..if cfg!(miri) { 1_000 } else { 1_000_000 } {
+            stack.push(i);
+        }
+    }
+
+    #[test]
+    fn stress() {
+        const THREADS: usize = 8;
+        let per_thread = if cfg!(miri) { 50 } else { 20_000 };
+
+        let stack = Arc::new(Stack::new());
+        let barrier = Arc::new(Barrier::new(2 * THREADS));
+        let producers: Vec<_> = (0..THREADS).map(|t| {
+            let (stack, barrier) = (stack.clone(), barrier.clone());
+            thread::spawn(move || {
+                barrier.wait();
+                for i in 0..per_thread {
+                    stack.push(t * per_thread + i);
+                }
+            })
+        }).collect();
+
+        let popped = Arc::new(AtomicUsize::new(0));
+        let consumers: Vec<_> = (0..THREADS).map(|_| {
+            let (stack, barrier, popped) = (stack.clone(), barrier.clone(), popped.clone());
+            thread::spawn(move || {
+                barrier.wait();
+                let mut mine = Vec::new();
+                while popped.load(Ordering::Relaxed) < THREADS * per_thread {
+                    if let Some(v) = stack.pop() {
+                        popped.fetch_add(1, Ordering::Relaxed);
+                        mine.push(v);
+                    } else {
+                        thread::yield_now();
+                    }
+                }
+                mine
+            })
+        }).collect();
+
+        for p in producers {
+            p.join().unwrap();
+        }
+        let mut all: Vec<usize> = consumers.into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+        all.sort();
+        // Every element came out exactly once.
+        assert_eq!(all, (0..THREADS * per_thread).collect::<Vec<_>>());
+        assert!(stack.is_empty());
+    }
+}
@@ -0,0 +1,429 @@
+//! An unrolled linked list: a stack like `first::List`, but each node holds
+//! up to `NODE_CAPACITY` elements, so there is one pointer and one
+//! allocation per block of elements instead of per element.
+//!
+//! Inside a node, elements are stored back to front: the node's first
+//! element is in the last occupied slot. That keeps `push` and `pop` at the
+//! head O(1), because they only touch the end of the head node's array.
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+/// Elements per node.
+pub const NODE_CAPACITY: usize = 16;
+
+pub struct List<T> {
+    head: Link<T>,
+    len: usize,
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+    /// `elems[..len]` are initialized; `elems[len - 1]` is the first.
+    elems: [MaybeUninit<T>; NODE_CAPACITY],
+    len: usize,
+    next: Link<T>,
+}
+
+impl<T> Node<T> {
+    fn new(next: Link<T>) -> Box<Self> {
+        Box::new(Node {
+            elems: ::std::array::from_fn(|_| MaybeUninit::uninit()),
+            len: 0,
+            next,
+        })
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == NODE_CAPACITY
+    }
+
+    /// Slot of the element at position `i` of this node.
+    fn slot(&self, i: usize) -> usize {
+        self.len - 1 - i
+    }
+
+    fn get(&self, i: usize) -> &T {
+        debug_assert!(i < self.len);
+        unsafe { &*self.elems[self.slot(i)].as_ptr() }
+    }
+
+    fn get_mut(&mut self, i: usize) -> &mut T {
+        debug_assert!(i < self.len);
+        let slot = self.slot(i);
+        unsafe { &mut *self.elems[slot].as_mut_ptr() }
+    }
+
+    /// Inserts `elem` at position `i` of a node with room for it.
+    fn insert(&mut self, i: usize, elem: T) {
+        debug_assert!(i <= self.len && !self.is_full());
+        // Positions before `i` sit in the slots above `len - i`; shift them
+        // up by one to make room.
+        let at = self.len - i;
+        unsafe {
+            let base = self.elems.as_mut_ptr();
+            ptr::copy(base.add(at), base.add(at + 1), i);
+            (*base.add(at)).as_mut_ptr().write(elem);
+        }
+        self.len += 1;
+    }
+
+    /// Moves the back half of this full node into a new node right after
+    /// it.
+    fn split(&mut self) {
+        debug_assert!(self.is_full());
+        let front = NODE_CAPACITY / 2;
+        let back = NODE_CAPACITY - front;
+        let mut new = Node::new(self.next.take());
+        unsafe {
+            // The back half is stored in the low slots, in the same order
+            // the new node wants them.
+            let base = self.elems.as_mut_ptr();
+            ptr::copy_nonoverlapping(base, new.elems.as_mut_ptr(), back);
+            ptr::copy(base.add(back), base, front);
+        }
+        new.len = back;
+        self.len = front;
+        self.next = Some(new);
+    }
+}
+
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        for elem in &mut self.elems[..self.len] {
+            unsafe { ptr::drop_in_place(elem.as_mut_ptr()) };
+        }
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { head: None, len: 0 }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let has_room = match self.head {
+            Some(ref node) => !node.is_full(),
+            None => false,
+        };
+        if !has_room {
+            let next = self.head.take();
+            self.head = Some(Node::new(next));
+        }
+        if let Some(ref mut node) = self.head {
+            node.insert(0, elem);
+        }
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let (elem, now_empty) = {
+            let node = self.head.as_mut()?;
+            node.len -= 1;
+            let elem = unsafe { node.elems[node.len].as_ptr().read() };
+            (elem, node.len == 0)
+        };
+        if now_empty {
+            let mut node = self.head.take().unwrap();
+            self.head = node.next.take();
+        }
+        self.len -= 1;
+        Some(elem)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| node.get(0))
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| node.get_mut(0))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The element at `index`, counting from the head. Skips whole nodes,
+    /// so this takes O(index / NODE_CAPACITY) steps.
+    pub fn get(&self, mut index: usize) -> Option<&T> {
+        let mut link = &self.head;
+        while let Some(ref node) = *link {
+            if index < node.len {
+                return Some(node.get(index));
+            }
+            index -= node.len;
+            link = &node.next;
+        }
+        None
+    }
+
+    pub fn get_mut(&mut self, mut index: usize) -> Option<&mut T> {
+        let mut link = &mut self.head;
+        while let Some(ref mut node) = *link {
+            if index < node.len {
+                return Some(node.get_mut(index));
+            }
+            index -= node.len;
+            link = &mut node.next;
+        }
+        None
+    }
+
+    /// Inserts `elem` so that it ends up at `index`. A full node is split in
+    /// two to make room, so this never shifts more than one node's worth
+    /// of elements.
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, mut index: usize, elem: T) {
+        assert!(index <= self.len, "insertion index {} out of bounds for length {}",
+                index, self.len);
+        if index == 0 {
+            return self.push(elem);
+        }
+        let mut node = self.head.as_mut().unwrap();
+        // Stop at the node that holds `index - 1`, so inserting right after
+        // a node's last element goes into that node.
+        while index > node.len {
+            index -= node.len;
+            node = node.next.as_mut().unwrap();
+        }
+        if node.is_full() {
+            node.split();
+            if index > node.len {
+                index -= node.len;
+                node = node.next.as_mut().unwrap();
+            }
+        }
+        node.insert(index, elem);
+        self.len += 1;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { node: self.head.as_deref(), pos: 0, len: self.len }
+    }
+
+    /// Number of nodes, for measuring how full they are.
+    pub fn num_nodes(&self) -> usize {
+        let mut count = 0;
+        let mut link = &self.head;
+        while let Some(ref node) = *link {
+            count += 1;
+            link = &node.next;
+        }
+        count
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        // Unlink nodes one at a time, as in `first::List`.
+        let mut link = self.head.take();
+        while let Some(mut node) = link {
+            link = node.next.take();
+        }
+    }
+}
+
+/// Pushes the elements in reverse, so the first one yielded is the head and
+/// every node but the head one is full.
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        let mut list = List::new();
+        for elem in elems.into_iter().rev() {
+            list.push(elem);
+        }
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct IntoIter<T>(List<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+pub struct Iter<'a, T: 'a> {
+    node: Option<&'a Node<T>>,
+    /// Position of the next element within `node`.
+    pos: usize,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.node?;
+        let elem = node.get(self.pos);
+        self.pos += 1;
+        if self.pos == node.len {
+            self.node = node.next.as_deref();
+            self.pos = 0;
+        }
+        self.len -= 1;
+        Some(elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{List, NODE_CAPACITY};
+    use std::rc::Rc;
+
+    #[test]
+    fn basics() {
+        let mut list = List::new();
+        assert_eq!(list.pop(), None);
+
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), Some(2));
+
+        list.push(4);
+        list.push(5);
+        assert_eq!(list.peek(), Some(&5));
+        *list.peek_mut().unwrap() = 50;
+        assert_eq!(list.pop(), Some(50));
+        assert_eq!(list.pop(), Some(4));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+        assert_eq!(list.peek(), None);
+    }
+
+    #[test]
+    fn spans_nodes() {
+        let n = 5 * NODE_CAPACITY + 3;
+        let mut list = List::new();
+        for i in 0..n {
+            list.push(i);
+        }
+        assert_eq!(list.len(), n);
+        assert_eq!(list.num_nodes(), 6);
+        assert_eq!(list.iter().len(), n);
+        assert!(list.iter().cloned().eq((0..n).rev()));
+        for i in 0..n {
+            assert_eq!(list.get(i), Some(&(n - 1 - i)));
+        }
+        assert_eq!(list.get(n), None);
+        *list.get_mut(NODE_CAPACITY + 1).unwrap() = 1000;
+        assert_eq!(list.get(NODE_CAPACITY + 1), Some(&1000));
+
+        for i in (0..n).rev() {
+            if i != n - 2 - NODE_CAPACITY {
+                assert_eq!(list.pop(), Some(i));
+            } else {
+                assert_eq!(list.pop(), Some(1000));
+            }
+        }
+        assert_eq!(list.num_nodes(), 0);
+    }
+
+    #[test]
+    fn insert_splits_nodes() {
+        let mut list = List::new();
+        let mut expected = Vec::new();
+        // Insert at a spread of positions, checking against a Vec.
+        let mut x = 7usize;
+        for i in 0..500 {
+            x = (x * 31 + 17) % 1009;
+            let index = x % (expected.len() + 1);
+            list.insert(index, i);
+            expected.insert(index, i);
+        }
+        assert_eq!(list.len(), 500);
+        assert_eq!(list.iter().cloned().collect::<Vec<_>>(), expected);
+        // Splits leave nodes at least half full.
+        assert!(list.num_nodes() <= 2 * 500 / NODE_CAPACITY + 1, "{}", list.num_nodes());
+
+        let mut list: List<i32> = vec![1, 2].into_iter().collect();
+        list.insert(2, 3);
+        list.insert(0, 0);
+        assert_eq!(format!("{:?}", list), "[0, 1, 2, 3]");
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        List::new().insert(1, 'x');
+    }
+
+    #[test]
+    fn drops_every_element_once() {
+        let shared = Rc::new(());
+        {
+            let mut list = List::new();
+            for i in 0..3 * NODE_CAPACITY {
+                list.insert(i / 2, shared.clone());
+            }
+            drop(list.pop());
+            assert_eq!(Rc::strong_count(&shared), 3 * NODE_CAPACITY);
+            let collected: Vec<_> = list.into_iter().take(5).collect();
+            assert_eq!(Rc::strong_count(&shared), 6);
+            drop(collected);
+        }
+        assert_eq!(Rc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn long_list() {
+        let n = if cfg!(miri) { 1_000 } else { 1_000_000 };
+        let list: List<u32> = (0..n).collect();
+        assert_eq!(list.get(n as usize - 1), Some(&(n - 1)));
+    }
+}
@@ -0,0 +1,16 @@
+//! Helpers shared by the benchmarks.
+
+use std::time::{Duration, Instant};
+
+/// Runs `f` repeatedly for about half a second and returns the mean time
+/// per call.
+pub fn time<F: FnMut()>(mut f: F) -> Duration {
+    let budget = Duration::from_millis(500);
+    let start = Instant::now();
+    let mut runs = 0u32;
+    while runs == 0 || start.elapsed() < budget {
+        f();
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
@@ -0,0 +1,68 @@
+//! Compares the crate's trees with std's `BTreeSet` on random and sorted
+//! insertions followed by lookups. Run with `cargo bench --bench sets`.
+
+extern crate bst;
+
+mod common;
+
+use std::collections::BTreeSet;
+use std::hint::black_box;
+use std::time::Duration;
+
+use bst::Set;
+use bst::avl::AVL;
+use bst::red_black::RedBlack;
+use bst::second::BST;
+use common::time;
+
+/// Builds a set from `keys`, then looks each of them up.
+fn build_and_search<S: Set<u64>>(keys: &[u64]) -> Duration {
+    time(|| {
+        let mut set = S::default();
+        for &k in keys {
+            set.insert(k);
+        }
+        for &k in keys {
+            black_box(set.search(&k));
+        }
+        black_box(set);
+    })
+}
+
+fn main() {
+    println!("{:>8} {:>8} {:>14} {:>14} {:>14} {:>14}",
+             "keys", "order", "bst", "avl", "red-black", "btree set");
+
+    // The plain BST recurses once per level, so sorted input would overflow
+    // the stack at the bigger size; it sits that one out.
+    for &(n, order) in &[(1_000, "random"), (100_000, "random"),
+                         (1_000, "sorted"), (100_000, "sorted")] {
+        let keys: Vec<u64> = if order == "sorted" {
+            (0..n).collect()
+        } else {
+            // Multiplying by an odd constant permutes the integers mod 2^64.
+            (0..n).map(|i: u64| i.wrapping_mul(0x9e37_79b9_7f4a_7c15)).collect()
+        };
+
+        let bst = if order == "sorted" && n > 1_000 {
+            "-".to_string()
+        } else {
+            format!("{:?}", build_and_search::<BST<u64>>(&keys))
+        };
+        let avl = build_and_search::<AVL<u64>>(&keys);
+        let red_black = build_and_search::<RedBlack<u64>>(&keys);
+        let btree = time(|| {
+            let mut set = BTreeSet::new();
+            for &k in &keys {
+                set.insert(k);
+            }
+            for &k in &keys {
+                black_box(set.contains(&k));
+            }
+            black_box(set);
+        });
+
+        println!("{:>8} {:>8} {:>14} {:>14?} {:>14?} {:>14?}",
+                 n, order, bst, avl, red_black, btree);
+    }
+}
@@ -0,0 +1,212 @@
+//! An AVL tree: a `BST` that rebalances itself on insertion, so sorted
+//! input still gives a tree of height O(log n).
+//!
+//! Every node records the height of its subtree. After an insertion, each
+//! node on the way back up whose children's heights differ by two is fixed
+//! with one or two rotations.
+
+use std::borrow::Borrow;
+use std::cmp::{self, Ordering};
+
+#[derive(Debug)]
+pub struct AVL<T> {
+    root: Link<T>,
+    len: usize
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+
+#[derive(Debug)]
+struct Node<T> {
+    elem: T,
+    /// Levels in the subtree rooted here; a leaf has height 1.
+    height: usize,
+    left: Link<T>,
+    right: Link<T>
+}
+
+fn height<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+impl<T> Node<T> {
+    fn update_height(&mut self) {
+        self.height = 1 + cmp::max(height(&self.left), height(&self.right));
+    }
+
+    /// Left height minus right height; AVL keeps it within -1..=1.
+    fn balance(&self) -> isize {
+        height(&self.left) as isize - height(&self.right) as isize
+    }
+}
+
+/// Lifts the left child into `node`'s place.
+fn rotate_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    node.update_height();
+    left.right = Some(node);
+    left.update_height();
+    left
+}
+
+/// Lifts the right child into `node`'s place.
+fn rotate_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    node.update_height();
+    right.left = Some(node);
+    right.update_height();
+    right
+}
+
+/// Restores the balance of `node`, whose subtrees are balanced but may
+/// differ in height by two.
+fn rebalance<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    node.update_height();
+    match node.balance() {
+        2 => {
+            // Left-right case: straighten the zig-zag into a left-left.
+            if node.left.as_ref().unwrap().balance() < 0 {
+                node.left = node.left.take().map(rotate_left);
+            }
+            rotate_right(node)
+        }
+        -2 => {
+            if node.right.as_ref().unwrap().balance() > 0 {
+                node.right = node.right.take().map(rotate_right);
+            }
+            rotate_left(node)
+        }
+        _ => node
+    }
+}
+
+fn insert<T: Ord>(link: &mut Link<T>, elem: T) -> bool {
+    match *link {
+        None => {
+            *link = Some(Box::new(Node{elem, height: 1, left: None, right: None}));
+            return true;
+        }
+        Some(ref mut node) => {
+            let inserted = match elem.cmp(&node.elem) {
+                Ordering::Equal => false,
+                Ordering::Less => insert(&mut node.left, elem),
+                Ordering::Greater => insert(&mut node.right, elem)
+            };
+            if !inserted {
+                return false;
+            }
+        }
+    }
+    *link = link.take().map(rebalance);
+    true
+}
+
+impl<T> AVL<T> {
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Levels in the tree; 0 when empty.
+    pub fn height(&self) -> usize { height(&self.root) }
+}
+
+impl<T> AVL<T> where T:Ord {
+    pub fn new() -> Self { AVL{root: None, len: 0} }
+
+    pub fn insert(&mut self, elem: T) -> bool {
+        let inserted = insert(&mut self.root, elem);
+        if inserted { self.len += 1; }
+        inserted
+    }
+
+    pub fn search<Q: ?Sized + Ord>(&self, elem: &Q) -> bool where T: Borrow<Q> {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            link = match elem.cmp(node.elem.borrow()) {
+                Ordering::Equal => return true,
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right
+            };
+        }
+        false
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> { self.into_iter() }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> { self.into_iter() }
+
+    /// Panics unless the elements are in strictly increasing order, every
+    /// stored height is right and no node's subtrees differ in height by
+    /// more than one. For tests and debugging; it visits every node.
+    pub fn check_invariants(&self) {
+        let mut prev: Option<&T> = None;
+        let count = check_node(&self.root, &mut prev);
+        assert_eq!(count, self.len, "len is {} but the tree holds {}", self.len, count);
+    }
+}
+
+/// Checks the subtree at `link`, whose elements must all come after
+/// `prev`, and returns how many elements it holds.
+fn check_node<'a, T: Ord>(link: &'a Link<T>, prev: &mut Option<&'a T>) -> usize {
+    let node = match *link {
+        None => return 0,
+        Some(ref node) => node
+    };
+    let left = check_node(&node.left, prev);
+    if let Some(prev) = *prev {
+        assert!(*prev < node.elem, "elements out of order");
+    }
+    *prev = Some(&node.elem);
+    let right = check_node(&node.right, prev);
+
+    let expected = 1 + cmp::max(height(&node.left), height(&node.right));
+    assert_eq!(node.height, expected, "stale height");
+    assert!(node.balance().abs() <= 1, "balance factor {}", node.balance());
+    1 + left + right
+}
+
+impl<T> Default for AVL<T> where T:Ord {
+    fn default() -> Self { AVL::new() }
+}
+
+in_order_iters!(AVL, Node);
+
+#[cfg(test)]
+mod test {
+    use super::{AVL, Node};
+    use test_util::{exercise_balanced, max_avl_height, Balanced};
+
+    impl Balanced for AVL<usize> {
+        fn height(&self) -> usize { AVL::height(self) }
+
+        fn check_invariants(&self) { AVL::check_invariants(self) }
+    }
+
+    #[test]
+    fn balanced_tree() {
+        exercise_balanced::<AVL<usize>>(max_avl_height);
+    }
+
+    #[test]
+    fn sorted_input_is_perfectly_balanced() {
+        // A perfectly balanced tree of 2^10 - 1 elements has 10 levels.
+        let mut avl = AVL::new();
+        for elem in 0..1023 {
+            avl.insert(elem);
+        }
+        assert_eq!(avl.height(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "balance factor")]
+    fn check_invariants_catches_imbalance() {
+        let leaf = |elem| Some(Box::new(Node { elem, height: 1, left: None, right: None }));
+        let mut middle = Box::new(Node { elem: 2, height: 0, left: None, right: leaf(3) });
+        middle.update_height();
+        let mut root = Box::new(Node { elem: 1, height: 0, left: None, right: Some(middle) });
+        root.update_height();
+        AVL { root: Some(root), len: 3 }.check_invariants();
+    }
+}
@@ -0,0 +1,173 @@
+//! In-order iteration shared by the trees.
+//!
+//! An iterator keeps the part of the tree it hasn't visited yet as a deque
+//! of whole subtrees and single elements, in order. `next` splits the front
+//! subtree until an element comes out on top, `next_back` does the same at
+//! the back; either way the deque holds at most one subtree and one element
+//! per level of the tree.
+
+use std::collections::VecDeque;
+
+/// A subtree that can be taken apart into its left subtree, its own
+/// element and its right subtree. Trees implement it for owned nodes and
+/// for shared and mutable references to them.
+pub trait Split: Sized {
+    type Elem;
+
+    fn split(self) -> (Option<Self>, Self::Elem, Option<Self>);
+}
+
+enum Piece<S: Split> {
+    Elem(S::Elem),
+    Tree(S),
+}
+
+pub struct InOrder<S: Split> {
+    pieces: VecDeque<Piece<S>>,
+    len: usize,
+}
+
+impl<S: Split> InOrder<S> {
+    /// Iterates over the `len` elements of the tree at `root`.
+    pub fn new(root: Option<S>, len: usize) -> Self {
+        InOrder { pieces: root.map(Piece::Tree).into_iter().collect(), len }
+    }
+}
+
+impl<S: Split> Iterator for InOrder<S> {
+    type Item = S::Elem;
+
+    fn next(&mut self) -> Option<S::Elem> {
+        loop {
+            match self.pieces.pop_front()? {
+                Piece::Elem(elem) => {
+                    self.len -= 1;
+                    return Some(elem);
+                }
+                Piece::Tree(tree) => {
+                    let (left, elem, right) = tree.split();
+                    if let Some(right) = right { self.pieces.push_front(Piece::Tree(right)); }
+                    self.pieces.push_front(Piece::Elem(elem));
+                    if let Some(left) = left { self.pieces.push_front(Piece::Tree(left)); }
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.len, Some(self.len)) }
+}
+
+impl<S: Split> DoubleEndedIterator for InOrder<S> {
+    fn next_back(&mut self) -> Option<S::Elem> {
+        loop {
+            match self.pieces.pop_back()? {
+                Piece::Elem(elem) => {
+                    self.len -= 1;
+                    return Some(elem);
+                }
+                Piece::Tree(tree) => {
+                    let (left, elem, right) = tree.split();
+                    if let Some(left) = left { self.pieces.push_back(Piece::Tree(left)); }
+                    self.pieces.push_back(Piece::Elem(elem));
+                    if let Some(right) = right { self.pieces.push_back(Piece::Tree(right)); }
+                }
+            }
+        }
+    }
+}
+
+impl<S: Split> ExactSizeIterator for InOrder<S> {}
+
+/// Implements the iterator traits for `$name`, a tuple struct wrapping an
+/// `InOrder` or another tree's iterator, so trees can expose iterators
+/// without exposing their nodes.
+macro_rules! forward_in_order {
+    ($name:ident<$($lt:lifetime,)* $($param:ident),*>, $item:ty) => {
+        impl<$($lt,)* $($param),*> Iterator for $name<$($lt,)* $($param),*> {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<$item> { self.0.next() }
+
+            fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+        }
+
+        impl<$($lt,)* $($param),*> DoubleEndedIterator for $name<$($lt,)* $($param),*> {
+            fn next_back(&mut self) -> Option<$item> { self.0.next_back() }
+        }
+
+        impl<$($lt,)* $($param),*> ExactSizeIterator for $name<$($lt,)* $($param),*> {}
+    };
+}
+
+/// Implements `Split` for `$node<T>`, which must have `elem`, `left` and
+/// `right` fields, and gives `$tree<T>`, which must have `root` and `len`
+/// fields, the iterators `IntoIter`, `Iter` and `IterMut` with their
+/// `IntoIterator` impls.
+macro_rules! in_order_iters {
+    ($tree:ident, $node:ident) => {
+        impl<T> $crate::iter::Split for Box<$node<T>> {
+            type Elem = T;
+
+            fn split(self) -> (Option<Self>, T, Option<Self>) {
+                let node = *self;
+                (node.left, node.elem, node.right)
+            }
+        }
+
+        impl<'a, T> $crate::iter::Split for &'a $node<T> {
+            type Elem = &'a T;
+
+            fn split(self) -> (Option<Self>, &'a T, Option<Self>) {
+                (self.left.as_deref(), &self.elem, self.right.as_deref())
+            }
+        }
+
+        impl<'a, T> $crate::iter::Split for &'a mut $node<T> {
+            type Elem = &'a mut T;
+
+            fn split(self) -> (Option<Self>, &'a mut T, Option<Self>) {
+                let $node { ref mut elem, ref mut left, ref mut right, .. } = *self;
+                (left.as_deref_mut(), elem, right.as_deref_mut())
+            }
+        }
+
+        pub struct IntoIter<T>($crate::iter::InOrder<Box<$node<T>>>);
+
+        forward_in_order!(IntoIter<T>, T);
+
+        impl<T> IntoIterator for $tree<T> {
+            type Item = T;
+            type IntoIter = IntoIter<T>;
+
+            fn into_iter(mut self) -> Self::IntoIter {
+                IntoIter($crate::iter::InOrder::new(self.root.take(), self.len))
+            }
+        }
+
+        pub struct Iter<'a, T: 'a>($crate::iter::InOrder<&'a $node<T>>);
+
+        forward_in_order!(Iter<'a, T>, &'a T);
+
+        impl<'a, T> IntoIterator for &'a $tree<T> {
+            type Item = &'a T;
+            type IntoIter = Iter<'a, T>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                Iter($crate::iter::InOrder::new(self.root.as_deref(), self.len))
+            }
+        }
+
+        pub struct IterMut<'a, T: 'a>($crate::iter::InOrder<&'a mut $node<T>>);
+
+        forward_in_order!(IterMut<'a, T>, &'a mut T);
+
+        impl<'a, T> IntoIterator for &'a mut $tree<T> {
+            type Item = &'a mut T;
+            type IntoIter = IterMut<'a, T>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                IterMut($crate::iter::InOrder::new(self.root.as_deref_mut(), self.len))
+            }
+        }
+    };
+}
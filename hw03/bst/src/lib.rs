@@ -1,4 +1,177 @@
 #![cfg_attr(feature="clippy", feature(plugin))]
 #![cfg_attr(feature="clippy", plugin(clippy))]
 
+use std::borrow::Borrow;
+
+#[macro_use]
+mod iter;
+
+pub mod avl;
+pub mod map;
+pub mod red_black;
 pub mod second;
+
+/// Helpers shared by the trees' tests.
+#[cfg(test)]
+pub(crate) mod test_util {
+    use Set;
+
+    /// A self-balancing tree under test.
+    pub trait Balanced: Set<usize> + IntoIterator<Item = usize> {
+        /// Levels in the tree; 0 when empty.
+        fn height(&self) -> usize;
+
+        /// Panics if the tree breaks one of its invariants.
+        fn check_invariants(&self);
+    }
+
+    /// The tests every balanced tree shares: lookups, random and sorted
+    /// input staying within `max_height(n)` levels for `n` elements, and
+    /// iteration both ways.
+    pub fn exercise_balanced<S>(max_height: fn(usize) -> usize)
+        where S: Balanced,
+              for<'a> &'a S: IntoIterator<Item = &'a usize>,
+              for<'a> <&'a S as IntoIterator>::IntoIter: DoubleEndedIterator + ExactSizeIterator,
+              for<'a> &'a mut S: IntoIterator<Item = &'a mut usize>
+    {
+        let mut tree = S::default();
+        assert!(!tree.search(&5));
+        assert!(tree.insert(5));
+        assert!(tree.insert(6));
+        assert!(tree.insert(2));
+        assert!(!tree.insert(5));
+        assert!(tree.search(&6));
+        assert!(!tree.search(&7));
+        assert_eq!(tree.len(), 3);
+        tree.check_invariants();
+
+        for seed in 0..10 {
+            let mut tree = S::default();
+            for (i, elem) in shuffled(300, seed).into_iter().enumerate() {
+                tree.insert(elem);
+                tree.check_invariants();
+                assert!(tree.height() <= max_height(i + 1));
+            }
+            assert!((&tree).into_iter().cloned().eq(0..300));
+        }
+
+        let n = 1_000_000;
+        let mut tree = S::default();
+        for elem in 0..n {
+            tree.insert(elem);
+        }
+        assert!(tree.height() <= max_height(n));
+        assert!(tree.search(&(n - 1)));
+        tree.check_invariants();
+
+        let mut tree = S::default();
+        for elem in (0..1000).rev() {
+            tree.insert(elem);
+            tree.check_invariants();
+        }
+        assert!(tree.height() <= max_height(1000));
+
+        let mut tree = S::default();
+        for elem in shuffled(100, 1) {
+            tree.insert(elem);
+        }
+        assert_eq!((&tree).into_iter().len(), 100);
+        assert!((&tree).into_iter().rev().cloned().eq((0..100).rev()));
+        for elem in &mut tree {
+            *elem *= 3;
+        }
+        assert!(tree.into_iter().eq((0..100).map(|elem| elem * 3)));
+    }
+
+    /// `0..n` in an order fixed by `seed`, via Fisher-Yates with an LCG.
+    pub fn shuffled(n: usize, seed: u64) -> Vec<usize> {
+        let mut elems: Vec<usize> = (0..n).collect();
+        let mut state = seed;
+        for i in (1..n).rev() {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let j = (state >> 33) as usize % (i + 1);
+            elems.swap(i, j);
+        }
+        elems
+    }
+
+    /// The most levels an AVL tree with `n` elements can have.
+    pub fn max_avl_height(n: usize) -> usize {
+        (1.4405 * ((n + 2) as f64).log2() - 0.3277) as usize
+    }
+
+    /// The most levels a red-black tree with `n` elements can have.
+    pub fn max_red_black_height(n: usize) -> usize {
+        (2.0 * ((n + 1) as f64).log2()) as usize
+    }
+}
+
+/// An ordered set of `T`s. Each of the crate's trees implements it, so code
+/// written against `S: Set<T>` can switch between them by type parameter.
+pub trait Set<T: Ord>: Default {
+    /// Adds `elem`, returning false if it was already present.
+    fn insert(&mut self, elem: T) -> bool;
+
+    fn search<Q: ?Sized + Ord>(&self, elem: &Q) -> bool where T: Borrow<Q>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+impl<T: Ord> Set<T> for second::BST<T> {
+    fn insert(&mut self, elem: T) -> bool { second::BST::insert(self, elem) }
+
+    fn search<Q: ?Sized + Ord>(&self, elem: &Q) -> bool where T: Borrow<Q> {
+        second::BST::search(self, elem)
+    }
+
+    fn len(&self) -> usize { second::BST::len(self) }
+}
+
+impl<T: Ord> Set<T> for avl::AVL<T> {
+    fn insert(&mut self, elem: T) -> bool { avl::AVL::insert(self, elem) }
+
+    fn search<Q: ?Sized + Ord>(&self, elem: &Q) -> bool where T: Borrow<Q> {
+        avl::AVL::search(self, elem)
+    }
+
+    fn len(&self) -> usize { avl::AVL::len(self) }
+}
+
+impl<T: Ord> Set<T> for red_black::RedBlack<T> {
+    fn insert(&mut self, elem: T) -> bool { red_black::RedBlack::insert(self, elem) }
+
+    fn search<Q: ?Sized + Ord>(&self, elem: &Q) -> bool where T: Borrow<Q> {
+        red_black::RedBlack::search(self, elem)
+    }
+
+    fn len(&self) -> usize { red_black::RedBlack::len(self) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Set;
+    use avl::AVL;
+    use red_black::RedBlack;
+    use second::BST;
+
+    /// Works the same on any tree; only the type parameter changes.
+    fn exercise<S: Set<u32>>() {
+        let mut set = S::default();
+        assert!(set.is_empty());
+        for elem in &[5, 1, 9, 3, 7, 5, 1] {
+            set.insert(*elem);
+        }
+        assert_eq!(set.len(), 5);
+        assert!(set.search(&7));
+        assert!(!set.search(&4));
+    }
+
+    #[test]
+    fn every_tree_is_a_set() {
+        exercise::<BST<u32>>();
+        exercise::<AVL<u32>>();
+        exercise::<RedBlack<u32>>();
+    }
+}
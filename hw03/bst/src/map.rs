@@ -0,0 +1,671 @@
+//! An ordered map on an unbalanced binary search tree. `second::BST` is
+//! this map with `()` values.
+//!
+//! Every node records the size of its subtree, so besides lookups the map
+//! answers order questions (`rank`, `select`) in one walk down the tree.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::Index;
+
+use iter::{InOrder, Split};
+
+pub struct BstMap<K, V> {
+    root: Link<K, V>
+}
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    /// Number of entries in the subtree rooted here, for `rank`/`select`.
+    size: usize,
+    left: Link<K, V>,
+    right: Link<K, V>
+}
+
+fn size<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+impl<K, V> BstMap<K, V> {
+    pub fn len(&self) -> usize { size(&self.root) }
+
+    pub fn is_empty(&self) -> bool { self.root.is_none() }
+
+    pub fn iter(&self) -> Iter<'_, K, V> { self.into_iter() }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> { self.into_iter() }
+
+    pub fn keys(&self) -> Keys<'_, K, V> { Keys(self.iter()) }
+
+    pub fn values(&self) -> Values<'_, K, V> { Values(self.iter()) }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> { ValuesMut(self.iter_mut()) }
+
+    pub fn into_keys(self) -> IntoKeys<K, V> { IntoKeys(self.into_iter()) }
+
+    /// Mutable references to the keys, for `BST::iter_mut`. Changing a key's
+    /// order relative to the others breaks the map.
+    pub(crate) fn keys_mut(&mut self) -> KeysMut<'_, K, V> {
+        let len = self.len();
+        KeysMut(InOrder::new(self.root.as_deref_mut().map(KeyMut), len))
+    }
+}
+
+impl<K, V> BstMap<K, V> where K: Ord {
+    pub fn new() -> Self { BstMap{root: None} }
+
+    /// Maps `key` to `value`, returning the value it replaces. The stored
+    /// key is left alone when there is one.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> { insert(&mut self.root, key, value) }
+
+    pub fn get<Q: ?Sized + Ord>(&self, key: &Q) -> Option<&V> where K: Borrow<Q> {
+        self.find(key).map(|node| &node.value)
+    }
+
+    pub fn get_mut<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<&mut V> where K: Borrow<Q> {
+        self.find_mut(key).map(|node| &mut node.value)
+    }
+
+    pub fn contains_key<Q: ?Sized + Ord>(&self, key: &Q) -> bool where K: Borrow<Q> {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<V> where K: Borrow<Q> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes `key` and returns the stored key with its value.
+    pub fn remove_entry<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<(K, V)>
+        where K: Borrow<Q>
+    {
+        // Assume `key` is in the tree, so every subtree on the way down
+        // loses one, and give those back if the search comes up empty.
+        let mut link = &mut self.root;
+        loop {
+            let ord = match *link {
+                None => {
+                    restore_sizes(&mut self.root, key);
+                    return None;
+                }
+                Some(ref node) => key.cmp(node.key.borrow())
+            };
+            if ord == Ordering::Equal {
+                break;
+            }
+            let node = link.as_mut().unwrap();
+            node.size -= 1;
+            link = if ord == Ordering::Less { &mut node.left } else { &mut node.right };
+        }
+
+        let mut node = link.take().unwrap();
+        *link = match (node.left.take(), node.right.take()) {
+            (None, right) => right,
+            (left, None) => left,
+            (left, mut right) => {
+                // Two children: the in-order successor, the smallest entry
+                // of the right subtree, takes the removed node's place.
+                let mut successor = pop_min_node(&mut right).unwrap();
+                successor.size = node.size - 1;
+                successor.left = left;
+                successor.right = right;
+                Some(successor)
+            }
+        };
+        Some((node.key, node.value))
+    }
+
+    /// The entry for `key`, for inserting or updating in place with one
+    /// call.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let mut link = &mut self.root;
+        let mut sizes = Vec::new();
+        loop {
+            let ord = match *link {
+                None => return Entry::Vacant(VacantEntry { link, sizes, key }),
+                Some(ref node) => key.cmp(&node.key)
+            };
+            let node = &mut **link.as_mut().unwrap();
+            if ord == Ordering::Equal {
+                return Entry::Occupied(OccupiedEntry { node });
+            }
+            let Node { ref mut size, ref mut left, ref mut right, .. } = *node;
+            sizes.push(size);
+            link = if ord == Ordering::Less { left } else { right };
+        }
+    }
+
+    /// The entry with the smallest key.
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// The entry with the largest key.
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref right) = node.right {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        pop_min_node(&mut self.root).map(|node| (node.key, node.value))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        pop_max_node(&mut self.root).map(|node| (node.key, node.value))
+    }
+
+    /// The entry with the greatest key less than or equal to `key`.
+    pub fn floor<Q: ?Sized + Ord>(&self, key: &Q) -> Option<(&K, &V)> where K: Borrow<Q> {
+        self.closest(key, true, true)
+    }
+
+    /// The entry with the least key greater than or equal to `key`.
+    pub fn ceiling<Q: ?Sized + Ord>(&self, key: &Q) -> Option<(&K, &V)> where K: Borrow<Q> {
+        self.closest(key, false, true)
+    }
+
+    /// The entry with the greatest key strictly less than `key`.
+    pub fn predecessor<Q: ?Sized + Ord>(&self, key: &Q) -> Option<(&K, &V)> where K: Borrow<Q> {
+        self.closest(key, true, false)
+    }
+
+    /// The entry with the least key strictly greater than `key`.
+    pub fn successor<Q: ?Sized + Ord>(&self, key: &Q) -> Option<(&K, &V)> where K: Borrow<Q> {
+        self.closest(key, false, false)
+    }
+
+    /// Number of keys less than `key`.
+    pub fn rank<Q: ?Sized + Ord>(&self, key: &Q) -> usize where K: Borrow<Q> {
+        let mut rank = 0;
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            match key.cmp(node.key.borrow()) {
+                Ordering::Less => link = &node.left,
+                Ordering::Equal => return rank + size(&node.left),
+                Ordering::Greater => {
+                    rank += size(&node.left) + 1;
+                    link = &node.right;
+                }
+            }
+        }
+        rank
+    }
+
+    /// The entry whose key has `rank` smaller keys (the smallest is 0), if
+    /// the map is big enough.
+    pub fn select(&self, mut rank: usize) -> Option<(&K, &V)> {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            let left = size(&node.left);
+            match rank.cmp(&left) {
+                Ordering::Less => link = &node.left,
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Greater => {
+                    rank -= left + 1;
+                    link = &node.right;
+                }
+            }
+        }
+        None
+    }
+
+    fn find<Q: ?Sized + Ord>(&self, key: &Q) -> Option<&Node<K, V>> where K: Borrow<Q> {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right
+            };
+        }
+        None
+    }
+
+    fn find_mut<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<&mut Node<K, V>> where K: Borrow<Q> {
+        let mut link = &mut self.root;
+        while let Some(ref mut node) = *link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => &mut node.left,
+                Ordering::Greater => &mut node.right
+            };
+        }
+        None
+    }
+
+    /// The entry nearest `key` from below (or from above, if not `below`),
+    /// which may have a key equal to `key` if `inclusive`.
+    fn closest<Q: ?Sized + Ord>(&self, key: &Q, below: bool, inclusive: bool) -> Option<(&K, &V)>
+        where K: Borrow<Q>
+    {
+        let mut best = None;
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            let ord = node.key.borrow().cmp(key);
+            if ord == Ordering::Equal && inclusive {
+                return Some((&node.key, &node.value));
+            }
+            // Anything on the wanted side is a candidate, and better ones
+            // can only lie between it and `key`.
+            if ord == Ordering::Less && below || ord == Ordering::Greater && !below {
+                best = Some((&node.key, &node.value));
+            }
+            link = match ord {
+                Ordering::Less => &node.right,
+                Ordering::Greater => &node.left,
+                Ordering::Equal => if below { &node.left } else { &node.right },
+            };
+        }
+        best
+    }
+}
+
+impl<K, V> Default for BstMap<K, V> where K: Ord {
+    fn default() -> Self { BstMap::new() }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for BstMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for BstMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = BstMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+/// Panics if `key` isn't in the map.
+impl<K, Q: ?Sized, V> Index<&Q> for BstMap<K, V> where K: Ord + Borrow<Q>, Q: Ord {
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not in map")
+    }
+}
+
+/// Adds back the one `remove_entry` took from every subtree on the search
+/// path of the missing `key`.
+fn restore_sizes<K: Borrow<Q>, V, Q: ?Sized + Ord>(mut link: &mut Link<K, V>, key: &Q) {
+    while let Some(ref mut node) = *link {
+        node.size += 1;
+        link = if key < node.key.borrow() { &mut node.left } else { &mut node.right };
+    }
+}
+
+/// Unlinks the smallest node of the subtree at `link`.
+fn pop_min_node<K, V>(mut link: &mut Link<K, V>) -> Option<Box<Node<K, V>>> {
+    link.as_ref()?;
+    while link.as_ref().unwrap().left.is_some() {
+        let node = link.as_mut().unwrap();
+        node.size -= 1;
+        link = &mut node.left;
+    }
+    let mut node = link.take().unwrap();
+    *link = node.right.take();
+    Some(node)
+}
+
+/// Unlinks the largest node of the subtree at `link`.
+fn pop_max_node<K, V>(mut link: &mut Link<K, V>) -> Option<Box<Node<K, V>>> {
+    link.as_ref()?;
+    while link.as_ref().unwrap().right.is_some() {
+        let node = link.as_mut().unwrap();
+        node.size -= 1;
+        link = &mut node.right;
+    }
+    let mut node = link.take().unwrap();
+    *link = node.left.take();
+    Some(node)
+}
+
+fn insert<K: Ord, V>(link: &mut Link<K, V>, key: K, value: V) -> Option<V> {
+    match *link {
+        None => {
+            // place the entry in this link
+            *link = Some(Box::new(Node{key, value, size: 1, left: None, right: None}));
+            None
+        }
+        Some(ref mut node) => {
+            let old = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(mem::replace(&mut node.value, value)),
+                Ordering::Less => insert(&mut node.left, key, value),
+                Ordering::Greater => insert(&mut node.right, key, value)
+            };
+            // only a new entry makes the subtree bigger
+            if old.is_none() { node.size += 1; }
+            old
+        }
+    }
+}
+
+/// A view into one key of a `BstMap`, from `BstMap::entry`.
+pub enum Entry<'a, K: 'a, V: 'a> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>)
+}
+
+pub struct OccupiedEntry<'a, K: 'a, V: 'a> {
+    node: &'a mut Node<K, V>
+}
+
+pub struct VacantEntry<'a, K: 'a, V: 'a> {
+    /// The empty link where `key` belongs.
+    link: &'a mut Link<K, V>,
+    /// The sizes of the subtrees on the way down, which gain one on insert.
+    sizes: Vec<&'a mut usize>,
+    key: K
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => entry.key()
+        }
+    }
+
+    /// The value, after inserting `default` if there was none.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// The value, after inserting `default()` if there was none.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default())
+        }
+    }
+
+    /// Runs `f` on the value if there is one.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(ref mut entry) = self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K { &self.node.key }
+
+    pub fn get(&self) -> &V { &self.node.value }
+
+    pub fn get_mut(&mut self) -> &mut V { &mut self.node.value }
+
+    pub fn into_mut(self) -> &'a mut V { &mut self.node.value }
+
+    /// Replaces the value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V { mem::replace(&mut self.node.value, value) }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K { &self.key }
+
+    pub fn into_key(self) -> K { self.key }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        for size in self.sizes {
+            *size += 1;
+        }
+        let link = self.link;
+        *link = Some(Box::new(Node{key: self.key, value, size: 1, left: None, right: None}));
+        &mut link.as_mut().unwrap().value
+    }
+}
+
+impl<K, V> Split for Box<Node<K, V>> {
+    type Elem = (K, V);
+
+    fn split(self) -> (Option<Self>, (K, V), Option<Self>) {
+        let node = *self;
+        (node.left, (node.key, node.value), node.right)
+    }
+}
+
+impl<'a, K, V> Split for &'a Node<K, V> {
+    type Elem = (&'a K, &'a V);
+
+    fn split(self) -> (Option<Self>, (&'a K, &'a V), Option<Self>) {
+        (self.left.as_deref(), (&self.key, &self.value), self.right.as_deref())
+    }
+}
+
+impl<'a, K, V> Split for &'a mut Node<K, V> {
+    type Elem = (&'a K, &'a mut V);
+
+    fn split(self) -> (Option<Self>, (&'a K, &'a mut V), Option<Self>) {
+        // Splitting the node into its fields hands each piece its own
+        // borrow.
+        let Node { ref key, ref mut value, ref mut left, ref mut right, .. } = *self;
+        (left.as_deref_mut(), (key, value), right.as_deref_mut())
+    }
+}
+
+/// A node whose key is handed out mutably, for `keys_mut`.
+pub(crate) struct KeyMut<'a, K: 'a, V: 'a>(&'a mut Node<K, V>);
+
+impl<'a, K, V> Split for KeyMut<'a, K, V> {
+    type Elem = &'a mut K;
+
+    fn split(self) -> (Option<Self>, &'a mut K, Option<Self>) {
+        let Node { ref mut key, ref mut left, ref mut right, .. } = *self.0;
+        (left.as_deref_mut().map(KeyMut), key, right.as_deref_mut().map(KeyMut))
+    }
+}
+
+pub struct IntoIter<K, V>(InOrder<Box<Node<K, V>>>);
+
+forward_in_order!(IntoIter<K, V>, (K, V));
+
+impl<K, V> IntoIterator for BstMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        let len = self.len();
+        IntoIter(InOrder::new(self.root.take(), len))
+    }
+}
+
+pub struct Iter<'a, K: 'a, V: 'a>(InOrder<&'a Node<K, V>>);
+
+forward_in_order!(Iter<'a, K, V>, (&'a K, &'a V));
+
+impl<'a, K, V> IntoIterator for &'a BstMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter(InOrder::new(self.root.as_deref(), self.len()))
+    }
+}
+
+pub struct IterMut<'a, K: 'a, V: 'a>(InOrder<&'a mut Node<K, V>>);
+
+forward_in_order!(IterMut<'a, K, V>, (&'a K, &'a mut V));
+
+impl<'a, K, V> IntoIterator for &'a mut BstMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let len = self.len();
+        IterMut(InOrder::new(self.root.as_deref_mut(), len))
+    }
+}
+
+pub(crate) struct KeysMut<'a, K: 'a, V: 'a>(InOrder<KeyMut<'a, K, V>>);
+
+forward_in_order!(KeysMut<'a, K, V>, &'a mut K);
+
+/// Wraps one of the entry iterators, keeping one half of each entry.
+macro_rules! project {
+    ($name:ident<$($lt:lifetime,)* K, V>, $inner:ty, $item:ty, $field:tt) => {
+        pub struct $name<$($lt,)* K: $($lt +)*, V: $($lt +)*>($inner);
+
+        impl<$($lt,)* K, V> Iterator for $name<$($lt,)* K, V> {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<$item> { self.0.next().map(|entry| entry.$field) }
+
+            fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+        }
+
+        impl<$($lt,)* K, V> DoubleEndedIterator for $name<$($lt,)* K, V> {
+            fn next_back(&mut self) -> Option<$item> { self.0.next_back().map(|entry| entry.$field) }
+        }
+
+        impl<$($lt,)* K, V> ExactSizeIterator for $name<$($lt,)* K, V> {}
+    };
+}
+
+project!(Keys<'a, K, V>, Iter<'a, K, V>, &'a K, 0);
+project!(Values<'a, K, V>, Iter<'a, K, V>, &'a V, 1);
+project!(ValuesMut<'a, K, V>, IterMut<'a, K, V>, &'a mut V, 1);
+project!(IntoKeys<K, V>, IntoIter<K, V>, K, 0);
+
+#[cfg(test)]
+mod test {
+    use super::{BstMap, Entry};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = BstMap::new();
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.insert(2, "two"), None);
+        assert_eq!(map.insert(1, "one"), None);
+        assert_eq!(map.insert(3, "three"), None);
+        assert_eq!(map.insert(2, "deux"), Some("two"));
+        assert_eq!(map.len(), 3);
+
+        assert_eq!(map.get(&2), Some(&"deux"));
+        assert!(map.contains_key(&3));
+        assert!(!map.contains_key(&4));
+        *map.get_mut(&1).unwrap() = "un";
+        assert_eq!(map[&1], "un");
+        assert_eq!(format!("{:?}", map), r#"{1: "un", 2: "deux", 3: "three"}"#);
+
+        assert_eq!(map.remove(&2), Some("deux"));
+        assert_eq!(map.remove(&2), None);
+        assert_eq!(map.remove_entry(&3), Some((3, "three")));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "key not in map")]
+    fn index_missing_key() {
+        let map: BstMap<i32, i32> = BstMap::new();
+        let _ = map[&1];
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let mut map = BstMap::new();
+        map.insert("b".to_string(), 2);
+        map.insert("a".to_string(), 1);
+        assert_eq!(map["a"], 1);
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.remove("a"), Some(1));
+    }
+
+    #[test]
+    fn entry_api() {
+        let mut counts = BstMap::new();
+        for word in "the cat saw the other cat and the dog".split(' ') {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        assert_eq!(counts["the"], 3);
+        assert_eq!(counts["cat"], 2);
+        assert_eq!(counts["dog"], 1);
+        assert_eq!(counts.len(), 6);
+
+        counts.entry("cat").and_modify(|n| *n *= 10).or_insert(0);
+        counts.entry("cow").and_modify(|n| *n *= 10).or_insert_with(|| 7);
+        assert_eq!(counts["cat"], 20);
+        assert_eq!(counts["cow"], 7);
+
+        match counts.entry("dog") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &"dog");
+                assert_eq!(entry.insert(5), 1);
+                assert_eq!(*entry.get(), 5);
+            }
+            Entry::Vacant(_) => panic!("dog is in the map"),
+        }
+        match counts.entry("emu") {
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), "emu"),
+            Entry::Occupied(_) => panic!("emu isn't in the map"),
+        }
+        assert!(!counts.contains_key("emu"));
+
+        // Entries inserted through `entry` keep the subtree sizes right.
+        for (rank, word) in counts.keys().enumerate() {
+            assert_eq!(counts.rank(word), rank);
+            assert_eq!(counts.select(rank).map(|(k, _)| k), Some(word));
+        }
+    }
+
+    #[test]
+    fn iterators() {
+        let mut map: BstMap<i32, char> = vec![(3, 'c'), (1, 'a'), (2, 'b')].into_iter().collect();
+        assert_eq!(map.keys().cloned().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(map.values().rev().cloned().collect::<String>(), "cba");
+        assert_eq!(map.values().len(), 3);
+        for value in map.values_mut() {
+            *value = value.to_ascii_uppercase();
+        }
+        for (key, value) in &mut map {
+            if *key == 2 {
+                *value = '_';
+            }
+        }
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&1, &'A'), (&2, &'_'), (&3, &'C')]);
+        assert_eq!(map.into_iter().rev().collect::<Vec<_>>(), vec![(3, 'C'), (2, '_'), (1, 'A')]);
+    }
+
+    #[test]
+    fn matches_btree_map() {
+        let mut map = BstMap::new();
+        let mut reference = BTreeMap::new();
+        let mut x = 1u32;
+        for _ in 0..5_000 {
+            x = x.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let key = (x >> 16) % 300;
+            match x % 3 {
+                0 => assert_eq!(map.remove(&key), reference.remove(&key)),
+                1 => assert_eq!(map.insert(key, x), reference.insert(key, x)),
+                _ => {
+                    *map.entry(key).or_insert(0) += 1;
+                    *reference.entry(key).or_insert(0) += 1;
+                }
+            }
+            assert_eq!(map.len(), reference.len());
+        }
+        assert!(map.iter().eq(reference.iter()));
+        assert_eq!(map.first_key_value(), reference.iter().next());
+        assert_eq!(map.last_key_value(), reference.iter().next_back());
+        assert_eq!(map.pop_first(), reference.pop_first());
+        assert_eq!(map.pop_last(), reference.pop_last());
+        assert!(map.into_keys().eq(reference.into_keys()));
+    }
+}
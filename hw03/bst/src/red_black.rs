@@ -0,0 +1,220 @@
+//! A left-leaning red-black tree (Sedgewick's variant): a `BST` kept
+//! balanced by coloring each node red or black.
+//!
+//! A red node is glued to its parent, and the two together act as one node
+//! of a 2-3 tree. Every path from the root down to a missing child passes
+//! the same number of black nodes, no red node has a red child, and red
+//! nodes are only ever left children, so the tree is at most twice as tall
+//! as a perfectly balanced one. Insertion restores all three with
+//! rotations and color flips on the way back up.
+
+use std::borrow::Borrow;
+use std::cmp::{self, Ordering};
+
+#[derive(Debug)]
+pub struct RedBlack<T> {
+    root: Link<T>,
+    len: usize
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    Red,
+    Black
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    elem: T,
+    /// Color of the link from the parent to this node.
+    color: Color,
+    left: Link<T>,
+    right: Link<T>
+}
+
+fn is_red<T>(link: &Link<T>) -> bool {
+    link.as_ref().is_some_and(|node| node.color == Color::Red)
+}
+
+/// Lifts the right child, which must be red, into `node`'s place.
+fn rotate_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    right.color = node.color;
+    node.color = Color::Red;
+    right.left = Some(node);
+    right
+}
+
+/// Lifts the left child, which must be red, into `node`'s place.
+fn rotate_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    left.color = node.color;
+    node.color = Color::Red;
+    left.right = Some(node);
+    left
+}
+
+/// Splits a temporary 4-node: both children turn black and `node` joins
+/// its own parent.
+fn flip_colors<T>(node: &mut Node<T>) {
+    node.color = Color::Red;
+    node.left.as_mut().unwrap().color = Color::Black;
+    node.right.as_mut().unwrap().color = Color::Black;
+}
+
+/// Restores the invariants at `node` after an insertion below it.
+fn fix_up<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    if is_red(&node.right) && !is_red(&node.left) {
+        node = rotate_left(node);
+    }
+    if is_red(&node.left) && is_red(&node.left.as_ref().unwrap().left) {
+        node = rotate_right(node);
+    }
+    if is_red(&node.left) && is_red(&node.right) {
+        flip_colors(&mut node);
+    }
+    node
+}
+
+fn insert<T: Ord>(link: &mut Link<T>, elem: T) -> bool {
+    match *link {
+        None => {
+            *link = Some(Box::new(Node{elem, color: Color::Red, left: None, right: None}));
+            return true;
+        }
+        Some(ref mut node) => {
+            let inserted = match elem.cmp(&node.elem) {
+                Ordering::Equal => false,
+                Ordering::Less => insert(&mut node.left, elem),
+                Ordering::Greater => insert(&mut node.right, elem)
+            };
+            if !inserted {
+                return false;
+            }
+        }
+    }
+    *link = link.take().map(fix_up);
+    true
+}
+
+fn height<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| 1 + cmp::max(height(&node.left), height(&node.right)))
+}
+
+impl<T> RedBlack<T> {
+    pub fn len(&self) -> usize { self.len }
+
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Levels in the tree; 0 when empty. Walks the whole tree.
+    pub fn height(&self) -> usize { height(&self.root) }
+}
+
+impl<T> RedBlack<T> where T:Ord {
+    pub fn new() -> Self { RedBlack{root: None, len: 0} }
+
+    pub fn insert(&mut self, elem: T) -> bool {
+        let inserted = insert(&mut self.root, elem);
+        if inserted { self.len += 1; }
+        if let Some(ref mut root) = self.root {
+            root.color = Color::Black;
+        }
+        inserted
+    }
+
+    pub fn search<Q: ?Sized + Ord>(&self, elem: &Q) -> bool where T: Borrow<Q> {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            link = match elem.cmp(node.elem.borrow()) {
+                Ordering::Equal => return true,
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right
+            };
+        }
+        false
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> { self.into_iter() }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> { self.into_iter() }
+
+    /// Panics unless the elements are in strictly increasing order, the
+    /// root is black, no red node has a red child, red nodes are only left
+    /// children and every path down has the same number of black nodes.
+    /// For tests and debugging; it visits every node.
+    pub fn check_invariants(&self) {
+        assert!(!is_red(&self.root), "red root");
+        let mut prev: Option<&T> = None;
+        let mut count = 0;
+        check_node(&self.root, &mut prev, &mut count);
+        assert_eq!(count, self.len, "len is {} but the tree holds {}", self.len, count);
+    }
+}
+
+/// Checks the subtree at `link`, whose elements must all come after
+/// `prev`, and returns its black height.
+fn check_node<'a, T: Ord>(link: &'a Link<T>, prev: &mut Option<&'a T>, count: &mut usize)
+    -> usize
+{
+    let node = match *link {
+        None => return 1,
+        Some(ref node) => node
+    };
+    let left = check_node(&node.left, prev, count);
+    if let Some(prev) = *prev {
+        assert!(*prev < node.elem, "elements out of order");
+    }
+    *prev = Some(&node.elem);
+    *count += 1;
+    let right = check_node(&node.right, prev, count);
+
+    if node.color == Color::Red {
+        assert!(!is_red(&node.left) && !is_red(&node.right), "red node with a red child");
+    }
+    assert!(!is_red(&node.right), "red right child");
+    assert_eq!(left, right, "black heights differ");
+    left + if node.color == Color::Black { 1 } else { 0 }
+}
+
+impl<T> Default for RedBlack<T> where T:Ord {
+    fn default() -> Self { RedBlack::new() }
+}
+
+in_order_iters!(RedBlack, Node);
+
+#[cfg(test)]
+mod test {
+    use super::{Color, Node, RedBlack};
+    use test_util::{exercise_balanced, max_red_black_height, Balanced};
+
+    impl Balanced for RedBlack<usize> {
+        fn height(&self) -> usize { RedBlack::height(self) }
+
+        fn check_invariants(&self) { RedBlack::check_invariants(self) }
+    }
+
+    #[test]
+    fn balanced_tree() {
+        exercise_balanced::<RedBlack<usize>>(max_red_black_height);
+    }
+
+    #[test]
+    #[should_panic(expected = "black heights differ")]
+    fn check_invariants_catches_black_height() {
+        let black = |elem| Some(Box::new(Node { elem, color: Color::Black, left: None, right: None }));
+        let root = Node { elem: 2, color: Color::Black, left: black(1), right: None };
+        RedBlack { root: Some(Box::new(root)), len: 2 }.check_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "red node with a red child")]
+    fn check_invariants_catches_red_red() {
+        let red = |elem, left| Some(Box::new(Node { elem, color: Color::Red, left, right: None }));
+        let root = Node { elem: 3, color: Color::Black, left: red(2, red(1, None)), right: None };
+        RedBlack { root: Some(Box::new(root)), len: 3 }.check_invariants();
+    }
+}
@@ -1,148 +1,125 @@
-use std::cmp::Ordering;
+use std::borrow::Borrow;
 
+use map::{self, BstMap};
+
+/// A set of `T`s: a `BstMap` with nothing stored alongside the keys.
 #[derive(Debug)]
 pub struct BST<T> {
-    root: Link<T>
+    map: BstMap<T, ()>
 }
 
-type Link<T> = Option<Box<Node<T>>>;
+impl<T> BST<T> {
+    pub fn len(&self) -> usize { self.map.len() }
 
-#[derive(Debug)]
-struct Node<T> {
-    elem: T,
-    left: Link<T>,
-    right: Link<T>
+    pub fn is_empty(&self) -> bool { self.map.is_empty() }
 }
 
 impl<T> BST<T> where T:Ord {
-    pub fn new() -> Self { BST{root: None} }
-
-    pub fn insert(&mut self, elem: T) -> bool { self.root.insert(elem) }
+    pub fn new() -> Self { BST{map: BstMap::new()} }
 
-    pub fn search(&self, elem: T) -> bool { self.root.search(elem) }
-}
+    pub fn insert(&mut self, elem: T) -> bool { self.map.insert(elem, ()).is_none() }
 
-trait Set<T> {
-    fn insert(&mut self, elem: T) -> bool;
-    fn search(&self, elem: T) -> bool;
-}
+    pub fn search<Q: ?Sized + Ord>(&self, elem: &Q) -> bool where T: Borrow<Q> { self.map.contains_key(elem) }
 
-impl<T> Set<T> for Link<T> where T:Ord {
-    fn insert(&mut self, elem: T) -> bool {
-        match *self {
-            None => {
-                // place the element in this link
-                *self = Some(Box::new(Node{elem: elem, left: None, right: None}));
-                true
-            }
-            Some(ref mut node) => {
-                match elem.cmp(&node.elem) {
-                    Ordering::Equal => false,                    // return false if the is in this node
-                    Ordering::Less => node.left.insert(elem),    // recurse to the left if the new value
-                                                                 // is less than the node's value
-                    Ordering::Greater => node.right.insert(elem) // recurse to the right if the new value
-                                                                 // is greater than the node's value
-                }
-            }
-        }
+    /// Removes the element equal to `elem` and returns it, if there is one.
+    pub fn remove<Q: ?Sized + Ord>(&mut self, elem: &Q) -> Option<T> where T: Borrow<Q> {
+        self.map.remove_entry(elem).map(|(elem, ())| elem)
     }
-    fn search(&self, elem: T) -> bool {
-        match *self {
-            None => false,
-            Some(ref node) => 
-                match elem.cmp(&node.elem) {
-                    Ordering::Equal => true,
-                    Ordering::Less => node.left.search(elem),
-                    Ordering::Greater => node.right.search(elem)
-                }
-        }
+
+    pub fn min(&self) -> Option<&T> { self.map.first_key_value().map(|(elem, _)| elem) }
+
+    pub fn max(&self) -> Option<&T> { self.map.last_key_value().map(|(elem, _)| elem) }
+
+    pub fn pop_min(&mut self) -> Option<T> { self.map.pop_first().map(|(elem, ())| elem) }
+
+    pub fn pop_max(&mut self) -> Option<T> { self.map.pop_last().map(|(elem, ())| elem) }
+
+    /// The greatest element less than or equal to `elem`.
+    pub fn floor<Q: ?Sized + Ord>(&self, elem: &Q) -> Option<&T> where T: Borrow<Q> {
+        self.map.floor(elem).map(|(elem, _)| elem)
     }
-}
 
-pub struct IntoIter<T>(Link<T>);
+    /// The least element greater than or equal to `elem`.
+    pub fn ceiling<Q: ?Sized + Ord>(&self, elem: &Q) -> Option<&T> where T: Borrow<Q> {
+        self.map.ceiling(elem).map(|(elem, _)| elem)
+    }
 
-impl<T> Iterator for IntoIter<T> {
-    type Item = T;
+    /// The greatest element strictly less than `elem`.
+    pub fn predecessor<Q: ?Sized + Ord>(&self, elem: &Q) -> Option<&T> where T: Borrow<Q> {
+        self.map.predecessor(elem).map(|(elem, _)| elem)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.take().map(
-            |boxed_node| {
-                let node = *boxed_node;
-                self.0 = node.right;
-                node.elem
-            }
-        )
+    /// The least element strictly greater than `elem`.
+    pub fn successor<Q: ?Sized + Ord>(&self, elem: &Q) -> Option<&T> where T: Borrow<Q> {
+        self.map.successor(elem).map(|(elem, _)| elem)
     }
+
+    /// Number of elements less than `elem`.
+    pub fn rank<Q: ?Sized + Ord>(&self, elem: &Q) -> usize where T: Borrow<Q> { self.map.rank(elem) }
+
+    /// The element with `rank` smaller elements (the smallest is 0), if the
+    /// tree is big enough.
+    pub fn select(&self, rank: usize) -> Option<&T> { self.map.select(rank).map(|(elem, _)| elem) }
+
+    pub fn iter(&self) -> Iter<'_, T> { self.into_iter() }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> { self.into_iter() }
+}
+
+impl<T> Default for BST<T> where T:Ord {
+    fn default() -> Self { BST::new() }
 }
 
+pub struct IntoIter<T>(map::IntoKeys<T, ()>);
+
+forward_in_order!(IntoIter<T>, T);
+
 impl<T> IntoIterator for BST<T> {
     type Item = T;
     type IntoIter = IntoIter<T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        IntoIter(self.root)
+        IntoIter(self.map.into_keys())
     }
 }
 
-pub struct Iter<'a, T: 'a>(Option<&'a Node<T>>);
+pub struct Iter<'a, T: 'a>(map::Keys<'a, T, ()>);
 
-impl<'a, T> Iterator for Iter<'a, T> {
-    type Item = &'a T;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.map(
-            |node| {
-                self.0 = node.right.as_ref().map(|boxed_node_ref| { &**boxed_node_ref } );
-                &node.elem
-            }
-        )
-    }
-}
+forward_in_order!(Iter<'a, T>, &'a T);
 
 impl<'a, T> IntoIterator for &'a BST<T> {
     type Item = &'a T;
     type IntoIter = Iter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        Iter(self.root.as_ref().map(|boxed_node_ref| { &**boxed_node_ref } ))
+        Iter(self.map.keys())
     }
-    
 }
 
-pub struct IterMut<'a, T: 'a>(Option<&'a mut Node<T>>);
-
-impl<'a, T> Iterator for IterMut<'a, T> {
-    type Item = &'a mut T;
+pub struct IterMut<'a, T: 'a>(map::KeysMut<'a, T, ()>);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.take().map(
-            |node| {
-                self.0 = node.right.as_mut().take().map(|boxed_node_ref| { &mut **boxed_node_ref } );
-                &mut node.elem
-            }
-        )
-    }
-}
+forward_in_order!(IterMut<'a, T>, &'a mut T);
 
 impl<'a, T> IntoIterator for &'a mut BST<T> {
     type Item = &'a mut T;
     type IntoIter = IterMut<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        IterMut(self.root.as_mut().take().map(|boxed_node_ref| { &mut **boxed_node_ref }))
+        IterMut(self.map.keys_mut())
     }
 }
 
-
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::print_with_newline)]
 mod test {
     use super::BST;
+    use test_util::shuffled;
 
     #[test]
     fn test_insert_search() {
         let mut bin_tree = BST::new();
 
-        assert_eq!(bin_tree.search(5), false);
+        assert_eq!(bin_tree.search(&5), false);
 
         assert_eq!(bin_tree.insert(5), true);
         assert_eq!(bin_tree.insert(6), true);
@@ -151,9 +128,9 @@ mod test {
 
         assert_eq!(bin_tree.insert(3), false);
 
-        assert_eq!(bin_tree.search(6), true);
-        assert_eq!(bin_tree.search(7), false);
-        assert_eq!(bin_tree.search(3), true);
+        assert_eq!(bin_tree.search(&6), true);
+        assert_eq!(bin_tree.search(&7), false);
+        assert_eq!(bin_tree.search(&3), true);
     }
 
     fn simple_bst() -> BST<i32> {
@@ -240,4 +217,152 @@ mod test {
         assert_eq!(iter.next(), Some(&mut 3i32));
         
     }
+
+    fn shuffled_bst(n: usize, seed: u64) -> BST<usize> {
+        let mut bst = BST::new();
+        for elem in shuffled(n, seed) {
+            assert!(bst.insert(elem));
+        }
+        bst
+    }
+
+    #[test]
+    fn iters_are_in_order() {
+        for seed in 0..20 {
+            let n = 1 + seed as usize * 13;
+            let sorted: Vec<usize> = (0..n).collect();
+            let mut bst = shuffled_bst(n, seed);
+
+            assert_eq!(bst.iter().len(), n);
+            assert_eq!(bst.iter().cloned().collect::<Vec<_>>(), sorted);
+            assert_eq!(bst.iter().rev().cloned().collect::<Vec<_>>(),
+                       sorted.iter().rev().cloned().collect::<Vec<_>>());
+
+            for elem in bst.iter_mut() {
+                *elem *= 2;
+            }
+            assert_eq!(bst.iter_mut().rev().map(|elem| *elem).collect::<Vec<_>>(),
+                       sorted.iter().rev().map(|elem| elem * 2).collect::<Vec<_>>());
+
+            assert_eq!(bst.into_iter().collect::<Vec<_>>(),
+                       sorted.iter().map(|elem| elem * 2).collect::<Vec<_>>());
+            assert_eq!(shuffled_bst(n, seed).into_iter().rev().collect::<Vec<_>>(),
+                       sorted.iter().rev().cloned().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn iters_meet_in_the_middle() {
+        let mut bst = shuffled_bst(100, 7);
+
+        let mut iter = bst.iter();
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        while let Some(&elem) = iter.next() {
+            front.push(elem);
+            assert_eq!(iter.len(), 100 - front.len() - back.len());
+            if let Some(&elem) = iter.next_back() {
+                back.push(elem);
+            }
+        }
+        assert_eq!(iter.next_back(), None);
+        back.reverse();
+        front.extend(back);
+        assert_eq!(front, (0..100).collect::<Vec<_>>());
+
+        let mut iter = bst.iter_mut();
+        assert_eq!(iter.next(), Some(&mut 0));
+        assert_eq!(iter.next_back(), Some(&mut 99));
+        assert_eq!(iter.len(), 98);
+        assert_eq!(iter.count(), 98);
+
+        let mut iter = bst.into_iter();
+        assert_eq!(iter.next_back(), Some(99));
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.len(), 98);
+    }
+
+    #[test]
+    fn empty_iters() {
+        let mut bst: BST<i32> = BST::new();
+        assert_eq!(bst.iter().next(), None);
+        assert_eq!(bst.iter_mut().next_back(), None);
+        assert_eq!(bst.into_iter().len(), 0);
+    }
+
+    #[test]
+    fn remove_matches_btree_set() {
+        use std::collections::BTreeSet;
+
+        for seed in 0..10 {
+            let mut bst = shuffled_bst(200, seed);
+            let mut reference: BTreeSet<usize> = (0..200).collect();
+            // Removing in another shuffled order hits leaves, one-child
+            // nodes and two-child nodes alike.
+            for (i, elem) in shuffled(250, seed + 100).into_iter().enumerate() {
+                assert_eq!(bst.remove(&elem), reference.take(&elem));
+                assert_eq!(bst.len(), reference.len());
+                if i % 25 == 0 {
+                    assert!(bst.iter().eq(reference.iter()));
+                }
+            }
+            assert!(bst.is_empty());
+        }
+    }
+
+    #[test]
+    fn min_max() {
+        let mut bst = shuffled_bst(50, 3);
+        assert_eq!(bst.min(), Some(&0));
+        assert_eq!(bst.max(), Some(&49));
+        assert_eq!(bst.pop_min(), Some(0));
+        assert_eq!(bst.pop_max(), Some(49));
+        assert_eq!(bst.len(), 48);
+        assert_eq!(bst.min(), Some(&1));
+        assert_eq!(bst.max(), Some(&48));
+        while bst.pop_max().is_some() {}
+        assert_eq!(bst.pop_min(), None);
+        assert_eq!(bst.min(), None);
+    }
+
+    #[test]
+    fn ordered_queries() {
+        use std::collections::BTreeSet;
+        use std::ops::Bound::{Excluded, Unbounded};
+
+        // Even numbers only, so odd queries fall between elements.
+        let mut bst = BST::new();
+        let mut reference = BTreeSet::new();
+        for elem in shuffled(50, 5) {
+            bst.insert(elem * 2);
+            reference.insert(elem * 2);
+        }
+
+        for q in 0..102 {
+            assert_eq!(bst.floor(&q), reference.range(..=q).next_back());
+            assert_eq!(bst.ceiling(&q), reference.range(q..).next());
+            assert_eq!(bst.predecessor(&q), reference.range(..q).next_back());
+            assert_eq!(bst.successor(&q), reference.range((Excluded(q), Unbounded)).next());
+            assert_eq!(bst.rank(&q), reference.range(..q).count());
+        }
+
+        for k in 0..50 {
+            assert_eq!(bst.select(k), Some(&(k * 2)));
+            assert_eq!(bst.rank(&(k * 2)), k);
+        }
+        assert_eq!(bst.select(50), None);
+    }
+
+    #[test]
+    fn borrowed_queries() {
+        let mut bst = BST::new();
+        for word in &["pear", "apple", "fig"] {
+            bst.insert(word.to_string());
+        }
+        assert!(bst.search("fig"));
+        assert_eq!(bst.ceiling("b").map(|s| s.as_str()), Some("fig"));
+        assert_eq!(bst.rank("g"), 2);
+        assert_eq!(bst.remove("apple"), Some("apple".to_string()));
+        assert_eq!(bst.len(), 2);
+    }
 }